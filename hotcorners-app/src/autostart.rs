@@ -0,0 +1,195 @@
+//! Starting hotcorners with Windows through the current user's `Run` registry key
+
+use std::{
+    env,
+    os::windows::ffi::OsStrExt,
+    path::{Path, PathBuf},
+};
+
+use windows::{
+    core::{w, Result, PCWSTR},
+    Win32::{
+        Foundation::{ERROR_FILE_NOT_FOUND, WIN32_ERROR},
+        System::Registry::{
+            RegCloseKey, RegDeleteValueW, RegGetValueW, RegOpenKeyExW, RegSetValueExW, HKEY,
+            HKEY_CURRENT_USER, KEY_SET_VALUE, REG_SAM_FLAGS, REG_SZ, RRF_RT_REG_SZ,
+        },
+    },
+};
+
+/// Key that lists the programs started when the current user logs in
+const RUN_KEY: PCWSTR = w!(r"Software\Microsoft\Windows\CurrentVersion\Run");
+/// Name of our value in `RUN_KEY`
+const VALUE_NAME: PCWSTR = w!("hotcorners");
+
+/// Registers the current executable to start when the current user logs in, returning its path
+pub fn install() -> std::result::Result<PathBuf, String> {
+    let exe = env::current_exe().map_err(|e| format!("Failed to find hotcorners.exe: {e}"))?;
+    write_command(RUN_KEY, VALUE_NAME, &exe)?;
+    Ok(exe)
+}
+
+/// Stops hotcorners from starting when the current user logs in. Returns `false` if it wasn't
+/// registered.
+pub fn uninstall() -> std::result::Result<bool, String> {
+    delete_command(RUN_KEY, VALUE_NAME)
+}
+
+/// The command registered to start hotcorners, if any
+pub fn status() -> std::result::Result<Option<String>, String> {
+    read_command(RUN_KEY, VALUE_NAME)
+}
+
+/// Sets the value `name` of `key` under `HKEY_CURRENT_USER` to the quoted path of `exe`
+fn write_command(key: PCWSTR, name: PCWSTR, exe: &Path) -> std::result::Result<(), String> {
+    // Quoted, since the path may contain spaces
+    let command: Vec<u16> = std::iter::once(u16::from(b'"'))
+        .chain(exe.as_os_str().encode_wide())
+        .chain([u16::from(b'"'), 0])
+        .collect();
+    let data: Vec<u8> = command.iter().flat_map(|c| c.to_le_bytes()).collect();
+
+    unsafe {
+        let key = open_key(key, KEY_SET_VALUE).map_err(|e| format!("Failed to open {e}"))?;
+        let result = RegSetValueExW(key, name, 0, REG_SZ, Some(&data));
+        let _ = RegCloseKey(key);
+        result
+            .ok()
+            .map_err(|e| format!("Failed to register hotcorners: {e}"))
+    }
+}
+
+/// Deletes the value `name` of `key` under `HKEY_CURRENT_USER`, returning `false` if it didn't
+/// exist
+fn delete_command(key: PCWSTR, name: PCWSTR) -> std::result::Result<bool, String> {
+    unsafe {
+        let key = open_key(key, KEY_SET_VALUE).map_err(|e| format!("Failed to open {e}"))?;
+        let result = RegDeleteValueW(key, name);
+        let _ = RegCloseKey(key);
+        if result == ERROR_FILE_NOT_FOUND {
+            return Ok(false);
+        }
+        result
+            .ok()
+            .map(|()| true)
+            .map_err(|e| format!("Failed to unregister hotcorners: {e}"))
+    }
+}
+
+/// The value `name` of `key` under `HKEY_CURRENT_USER`, if it exists
+fn read_command(key: PCWSTR, name: PCWSTR) -> std::result::Result<Option<String>, String> {
+    let mut len = 0u32;
+    unsafe {
+        // Ask for the size first
+        let result = get_value(key, name, None, &mut len);
+        if result == ERROR_FILE_NOT_FOUND {
+            return Ok(None);
+        }
+        result
+            .ok()
+            .map_err(|e| format!("Failed to read the Run key: {e}"))?;
+
+        let mut buffer = vec![0u16; len as usize / 2];
+        get_value(key, name, Some(&mut buffer), &mut len)
+            .ok()
+            .map_err(|e| format!("Failed to read the Run key: {e}"))?;
+
+        let value = String::from_utf16_lossy(&buffer[..len as usize / 2]);
+        Ok(Some(value.trim_end_matches('\0').to_owned()))
+    }
+}
+
+/// Opens `path` under `HKEY_CURRENT_USER` with `access`
+unsafe fn open_key(path: PCWSTR, access: REG_SAM_FLAGS) -> Result<HKEY> {
+    let mut key = HKEY::default();
+    RegOpenKeyExW(HKEY_CURRENT_USER, path, 0, access, &raw mut key).ok()?;
+    Ok(key)
+}
+
+/// Reads the value `name` of `key` under `HKEY_CURRENT_USER` into `buffer`, or just its size in
+/// bytes into `len` if `buffer` is `None`
+unsafe fn get_value(
+    key: PCWSTR,
+    name: PCWSTR,
+    buffer: Option<&mut [u16]>,
+    len: &mut u32,
+) -> WIN32_ERROR {
+    RegGetValueW(
+        HKEY_CURRENT_USER,
+        key,
+        name,
+        RRF_RT_REG_SZ,
+        None,
+        buffer.map(|buffer| buffer.as_mut_ptr().cast()),
+        Some(len),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use windows::Win32::System::Registry::{
+        RegCreateKeyExW, RegDeleteKeyW, RegDeleteTreeW, KEY_ALL_ACCESS, REG_OPTION_VOLATILE,
+    };
+
+    use super::*;
+
+    /// Parent of `TEST_KEY`, deleted with everything in it after the test
+    const TEST_ROOT: PCWSTR = w!(r"Software\hotcorners-tests");
+    /// Key the test writes to instead of `RUN_KEY`
+    const TEST_KEY: PCWSTR = w!(r"Software\hotcorners-tests\autostart");
+
+    /// `TEST_KEY`, deleted again when dropped, even if the test fails
+    struct TestKey;
+
+    impl TestKey {
+        /// Creates `TEST_KEY`, volatile so it's gone after a reboot even if the test crashed
+        fn create() -> Self {
+            let mut key = HKEY::default();
+            unsafe {
+                RegCreateKeyExW(
+                    HKEY_CURRENT_USER,
+                    TEST_KEY,
+                    0,
+                    None,
+                    REG_OPTION_VOLATILE,
+                    KEY_ALL_ACCESS,
+                    None,
+                    &raw mut key,
+                    None,
+                )
+                .ok()
+                .expect("the test key can be created");
+                let _ = RegCloseKey(key);
+            }
+            Self
+        }
+    }
+
+    impl Drop for TestKey {
+        fn drop(&mut self) {
+            // `RegDeleteTreeW` only deletes what is inside the key
+            unsafe {
+                let _ = RegDeleteTreeW(HKEY_CURRENT_USER, TEST_ROOT);
+                let _ = RegDeleteKeyW(HKEY_CURRENT_USER, TEST_ROOT);
+            }
+        }
+    }
+
+    #[test]
+    fn writes_reads_and_deletes_the_command() {
+        let _key = TestKey::create();
+        let exe = Path::new(r"C:\Program Files\hotcorners\hotcorners.exe");
+
+        assert_eq!(read_command(TEST_KEY, VALUE_NAME), Ok(None));
+        write_command(TEST_KEY, VALUE_NAME, exe).unwrap();
+        assert_eq!(
+            read_command(TEST_KEY, VALUE_NAME),
+            Ok(Some(
+                r#""C:\Program Files\hotcorners\hotcorners.exe""#.to_owned()
+            ))
+        );
+        assert_eq!(delete_command(TEST_KEY, VALUE_NAME), Ok(true));
+        assert_eq!(delete_command(TEST_KEY, VALUE_NAME), Ok(false));
+        assert_eq!(read_command(TEST_KEY, VALUE_NAME), Ok(None));
+    }
+}
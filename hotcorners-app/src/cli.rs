@@ -0,0 +1,291 @@
+//! Command-line flags
+
+use std::path::PathBuf;
+
+use windows::Win32::System::Console::{AttachConsole, ATTACH_PARENT_PROCESS};
+
+use hotcorners_core::{config, corners::Corner};
+
+use crate::{
+    autostart, diagnose,
+    ipc::{self, ClientError, Request, Response},
+    service::{self, StartType},
+};
+
+/// Exit code when a control command finds no running instance
+const EXIT_NOT_RUNNING: i32 = 2;
+
+/// What hotcorners was asked to do on the command line
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    /// Run the hot corners, the default without any flags
+    Run,
+    /// `--install`: start hotcorners when the current user logs in
+    Install,
+    /// `--uninstall`: stop starting hotcorners when the current user logs in
+    Uninstall,
+    /// `--install-service`: register hotcorners as a Windows service
+    InstallService,
+    /// `--uninstall-service`: stop and remove the Windows service
+    UninstallService,
+    /// `--run-service`: run as the Windows service, only used by the service control manager
+    RunService,
+    /// `--status`: print the state of the running instance and whether hotcorners starts when the
+    /// current user logs in
+    Status,
+    /// `--reload`: make the running instance reload `config.toml`
+    Reload,
+    /// `--disable <corner>`: stop a corner of the running instance from activating
+    Disable(Corner),
+    /// `--enable <corner>`: undo `--disable`
+    Enable(Corner),
+    /// `--init`: write a commented default `config.toml`, replacing an existing one only with
+    /// `--force`
+    Init,
+    /// `--print-config-path`: print which configuration file would be used
+    PrintConfigPath,
+    /// `--diagnose`: print a report of the monitors, the configuration and whether the mouse
+    /// hook can be installed
+    Diagnose,
+}
+
+/// Parsed command-line arguments
+#[derive(Debug, Clone, PartialEq, Eq)]
+// One bool per flag
+#[allow(clippy::struct_excessive_bools)]
+pub struct Args {
+    /// What to do
+    pub command: Command,
+    /// `--debug`: log what the hook and the worker threads are doing to stderr
+    pub debug: bool,
+    /// `--simulate`: print what the corners would do instead of doing it, for 30 seconds
+    pub simulate: bool,
+    /// `--config <path>`: read this configuration file instead of the default one
+    pub config: Option<PathBuf>,
+    /// `--force`: let `--init` replace an existing file
+    pub force: bool,
+    /// `--replace`: make a running instance exit instead of refusing to start
+    pub replace: bool,
+    /// `--instance-name <name>`: run or control an instance that runs next to the unnamed one
+    pub instance_name: Option<String>,
+    /// `--start-type <type>`: when Windows starts the service registered with
+    /// `--install-service`
+    pub start_type: StartType,
+}
+
+/// Parses the command-line arguments, without the executable name
+pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Args, String> {
+    let mut args = args.into_iter();
+    let mut command = Command::Run;
+    let mut debug = false;
+    let mut simulate = false;
+    let mut config = None;
+    let mut force = false;
+    let mut replace = false;
+    let mut instance_name = None;
+    let mut start_type = StartType::default();
+    while let Some(arg) = args.next() {
+        let flag = match arg.as_str() {
+            "--debug" => {
+                debug = true;
+                continue;
+            }
+            "--simulate" => {
+                simulate = true;
+                continue;
+            }
+            "--config" => {
+                let path = args
+                    .next()
+                    .ok_or_else(|| format!("{arg} needs a path, e.g. {arg} config.toml"))?;
+                config = Some(PathBuf::from(path));
+                continue;
+            }
+            "--force" => {
+                force = true;
+                continue;
+            }
+            "--replace" => {
+                replace = true;
+                continue;
+            }
+            "--instance-name" => {
+                let name = args
+                    .next()
+                    .filter(|name| !name.is_empty())
+                    .ok_or_else(|| format!("{arg} needs a name, e.g. {arg} work"))?;
+                instance_name = Some(name);
+                continue;
+            }
+            "--start-type" => {
+                start_type = args
+                    .next()
+                    .ok_or_else(|| format!("{arg} needs a start type, e.g. {arg} demand"))?
+                    .parse()?;
+                continue;
+            }
+            "--init" => Command::Init,
+            "--print-config-path" => Command::PrintConfigPath,
+            "--diagnose" => Command::Diagnose,
+            "--install" => Command::Install,
+            "--uninstall" => Command::Uninstall,
+            "--install-service" => Command::InstallService,
+            "--uninstall-service" => Command::UninstallService,
+            "--run-service" => Command::RunService,
+            "--status" => Command::Status,
+            "--reload" => Command::Reload,
+            "--disable" => Command::Disable(corner_arg(&arg, args.next())?),
+            "--enable" => Command::Enable(corner_arg(&arg, args.next())?),
+            _ => return Err(format!("Unknown argument: {arg}")),
+        };
+        if command != Command::Run {
+            return Err("Only one command can be used at a time".to_owned());
+        }
+        command = flag;
+    }
+
+    Ok(Args {
+        command,
+        debug,
+        simulate,
+        config,
+        force,
+        replace,
+        instance_name,
+        start_type,
+    })
+}
+
+/// Parses the corner following `flag`
+fn corner_arg(flag: &str, value: Option<String>) -> Result<Corner, String> {
+    value
+        .ok_or_else(|| format!("{flag} needs a corner, e.g. {flag} top-left"))?
+        .parse()
+}
+
+/// Runs a command other than `Command::Run` with the flags in `args`, returning the process exit
+/// code
+pub fn run(args: &Args) -> i32 {
+    attach_console();
+
+    match args.command {
+        Command::Run => 0,
+        Command::Install => exit_code(autostart::install().map(|exe| {
+            println!("hotcorners will start with Windows from {}", exe.display());
+        })),
+        Command::Uninstall => exit_code(autostart::uninstall().map(|removed| {
+            if removed {
+                println!("hotcorners will no longer start with Windows");
+            } else {
+                println!("hotcorners wasn't set to start with Windows");
+            }
+        })),
+        Command::InstallService => exit_code(service::install(args.start_type).map(|exe| {
+            println!("Installed the hotcorners service for {}", exe.display());
+        })),
+        Command::UninstallService => exit_code(service::uninstall().map(|removed| {
+            if removed {
+                println!("Uninstalled the hotcorners service");
+            } else {
+                println!("The hotcorners service wasn't installed");
+            }
+        })),
+        Command::RunService => exit_code(service::run()),
+        Command::Status => {
+            let code = send(Request::Status);
+            code.max(exit_code(print_autostart()))
+        }
+        Command::Reload => send(Request::Reload),
+        Command::Disable(corner) => send(Request::Disable { corner }),
+        Command::Enable(corner) => send(Request::Enable { corner }),
+        Command::PrintConfigPath => match config::resolve_config_path() {
+            Some(path) => {
+                let exists = if path.is_file() {
+                    ""
+                } else {
+                    " (doesn't exist)"
+                };
+                println!("{}{exists}", path.display());
+                0
+            }
+            None => exit_code(Err("%APPDATA% is not set".to_owned())),
+        },
+        Command::Diagnose => diagnose::run(),
+        Command::Init => exit_code(config::init(args.force).map(|path| {
+            println!("Wrote {}", path.display());
+        })),
+    }
+}
+
+/// Prints the error of a failed command, returning the process exit code
+fn exit_code(result: Result<(), String>) -> i32 {
+    match result {
+        Ok(()) => 0,
+        Err(e) => {
+            println!("{e}");
+            1
+        }
+    }
+}
+
+/// Sends `request` to the running instance and prints the response, returning the process exit
+/// code
+fn send(request: Request) -> i32 {
+    let cmd = serde_json::to_string(&request).expect("requests can always be serialized");
+    match ipc::connect_and_command(&cmd) {
+        Ok(response) => {
+            print_response(&response);
+            i32::from(!response.ok)
+        }
+        Err(e @ ClientError::NotRunning) => {
+            println!("{e}");
+            EXIT_NOT_RUNNING
+        }
+        Err(e) => {
+            println!("{e}");
+            1
+        }
+    }
+}
+
+/// Prints a response from the running instance
+fn print_response(response: &Response) {
+    if let Some(message) = &response.message {
+        println!("{message}");
+    }
+    if let Some(error) = &response.error {
+        println!("{error}");
+    }
+    for corner in response.corners.iter().flatten() {
+        let [left, top, right, bottom] = corner.rect;
+        let results = format!("{} succeeded, {} failed", corner.succeeded, corner.failed);
+        println!(
+            "  {} ({left}, {top}) - ({right}, {bottom}): {}, activated {} time{} ({results})",
+            corner.corner,
+            if corner.enabled {
+                "enabled"
+            } else {
+                "disabled"
+            },
+            corner.activations,
+            if corner.activations == 1 { "" } else { "s" }
+        );
+    }
+}
+
+/// Prints whether hotcorners starts when the current user logs in
+fn print_autostart() -> Result<(), String> {
+    match autostart::status()? {
+        Some(command) => println!("hotcorners starts with Windows: {command}"),
+        None => println!("hotcorners doesn't start with Windows"),
+    }
+    Ok(())
+}
+
+/// We're a GUI application without a console of our own, so print to the console we were
+/// started from, if any
+pub fn attach_console() {
+    unsafe {
+        let _ = AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+}
@@ -0,0 +1,181 @@
+//! `--diagnose`: a report of what hotcorners sees, to attach to bug reports
+
+use windows::Win32::{
+    Foundation::RECT,
+    System::LibraryLoader::GetModuleHandleW,
+    UI::{
+        HiDpi::{SetProcessDpiAwarenessContext, DPI_AWARENESS_CONTEXT_PER_MONITOR_AWARE_V2},
+        WindowsAndMessaging::{
+            GetSystemMetrics, SM_CXVIRTUALSCREEN, SM_CYVIRTUALSCREEN, SM_XVIRTUALSCREEN,
+            SM_YVIRTUALSCREEN,
+        },
+    },
+};
+
+use hotcorners_core::{
+    actions::{self, WINDOWS_11_BUILD},
+    config,
+    corners::{rebuild_corner_zones, DEFAULT_DPI},
+    monitors::enumerate_monitors,
+};
+
+use crate::instance;
+
+/// Prints the report, returning the process exit code: 1 if it found potential problems, 0
+/// otherwise
+pub fn run() -> i32 {
+    let mut problems = Vec::new();
+
+    // Like a running instance, so the coordinates below are the ones the hook sees
+    match unsafe { SetProcessDpiAwarenessContext(DPI_AWARENESS_CONTEXT_PER_MONITOR_AWARE_V2) } {
+        Ok(()) => println!("DPI awareness: per monitor"),
+        Err(e) => {
+            println!("DPI awareness: unaware ({e})");
+            problems.push("Not DPI aware, corners will be misplaced on scaled monitors".to_owned());
+        }
+    }
+
+    match actions::windows_build() {
+        Some(build) => {
+            let version = if build >= WINDOWS_11_BUILD { 11 } else { 10 };
+            println!("Windows: {version} (build {build})");
+        }
+        None => println!("Windows: unknown version"),
+    }
+
+    print_monitors();
+
+    match instance::acquire() {
+        Ok(Some(_guard)) => println!("Running instance: none"),
+        Ok(None) => println!("Running instance: yes"),
+        Err(e) => println!("Running instance: unknown ({e})"),
+    }
+
+    let config = match config::resolve_config_path() {
+        Some(path) if path.is_file() => {
+            println!("Config file: {}", path.display());
+            config::load()
+        }
+        Some(path) => {
+            println!("Config file: {} (doesn't exist)", path.display());
+            problems.push("No config file, the default corners are used".to_owned());
+            config::load()
+        }
+        None => {
+            println!("Config file: none, %APPDATA% is not set");
+            problems.push("No config file, the default corners are used".to_owned());
+            Ok(config::Config::default())
+        }
+    };
+    match config {
+        Ok(config) => {
+            println!("Config: valid");
+            print_corners(&config, &mut problems);
+        }
+        Err(e) => {
+            println!("Config: invalid");
+            problems.push(e.to_string());
+        }
+    }
+
+    check_hook(&mut problems);
+
+    if problems.is_empty() {
+        println!("\nNo problems found");
+        0
+    } else {
+        println!("\nPotential problems:");
+        for problem in &problems {
+            println!("  {problem}");
+        }
+        1
+    }
+}
+
+/// Prints every monitor with its DPI, and the bounds of the virtual desktop
+fn print_monitors() {
+    println!("Monitors:");
+    for monitor in enumerate_monitors() {
+        println!(
+            "  {}. {} {} at {}% ({} DPI){}",
+            monitor.index,
+            monitor.device_name,
+            format_rect(&monitor.bounds),
+            monitor.dpi * 100 / DEFAULT_DPI,
+            monitor.dpi,
+            if monitor.is_primary { ", primary" } else { "" }
+        );
+    }
+    let (x, y, width, height) = unsafe {
+        (
+            GetSystemMetrics(SM_XVIRTUALSCREEN),
+            GetSystemMetrics(SM_YVIRTUALSCREEN),
+            GetSystemMetrics(SM_CXVIRTUALSCREEN),
+            GetSystemMetrics(SM_CYVIRTUALSCREEN),
+        )
+    };
+    println!(
+        "Virtual desktop: ({x}, {y}) - ({}, {})",
+        x + width,
+        y + height
+    );
+}
+
+/// Prints the module handle and whether the mouse hook can be installed
+fn check_hook(problems: &mut Vec<String>) {
+    match unsafe { GetModuleHandleW(None) } {
+        Ok(module) if !module.is_invalid() => println!("Module handle: {:?}", module.0),
+        Ok(_) => {
+            println!("Module handle: null");
+            problems.push(
+                "GetModuleHandleW returned null, the hook would be installed with \
+                 HINSTANCE::default()"
+                    .to_owned(),
+            );
+        }
+        Err(e) => {
+            println!("Module handle: none ({e})");
+            problems.push(format!("GetModuleHandleW failed: {e}"));
+        }
+    }
+    // Removed again right away, the guard is dropped at the end of the statement
+    match crate::install_mouse_hook() {
+        Ok(_hook) => println!("Mouse hook: installs"),
+        Err(e) => {
+            println!("Mouse hook: fails ({e})");
+            problems.push(format!(
+                "SetWindowsHookExW failed, corners can't activate: {e}"
+            ));
+        }
+    }
+}
+
+/// Prints the corners of the default profile of `config` in physical coordinates
+fn print_corners(config: &config::Config, problems: &mut Vec<String>) {
+    match rebuild_corner_zones(config, config.default_profile()) {
+        Ok(zones) if zones.is_empty() => {
+            println!("Corners: none");
+            problems.push("No corners are configured".to_owned());
+        }
+        Ok(zones) => {
+            println!("Corners:");
+            for zone in zones {
+                println!(
+                    "  {} {}: {}",
+                    zone.config.position,
+                    format_rect(&zone.rect),
+                    zone.config.action.name()
+                );
+            }
+        }
+        Err(e) => problems.push(e),
+    }
+}
+
+/// `rect` as `(left, top) - (right, bottom)`
+fn format_rect(rect: &RECT) -> String {
+    format!(
+        "({}, {}) - ({}, {})",
+        rect.left, rect.top, rect.right, rect.bottom
+    )
+}
@@ -0,0 +1,82 @@
+//! Makes sure only one instance runs at a time, or one per `--instance-name`
+
+use std::{sync::OnceLock, thread, time::Duration};
+
+use windows::{
+    core::HSTRING,
+    Win32::{
+        Foundation::{CloseHandle, GetLastError, ERROR_ALREADY_EXISTS, HANDLE},
+        System::Threading::CreateMutexW,
+    },
+};
+
+/// How long `--replace` waits for the running instance to exit
+const REPLACE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How often `--replace` checks whether the running instance exited
+const REPLACE_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Name given with `--instance-name`
+static NAME: OnceLock<String> = OnceLock::new();
+
+/// Holds the single-instance mutex until dropped
+#[derive(Debug)]
+pub struct InstanceGuard(HANDLE);
+
+impl Drop for InstanceGuard {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = CloseHandle(self.0);
+        }
+    }
+}
+
+/// Sets the name from `--instance-name`. Instances with different names run side by side, and
+/// control commands only reach the instance with the same name.
+pub fn set_name(name: String) {
+    let _ = NAME.set(name);
+}
+
+/// Name of the named pipe of this instance
+pub fn pipe_name() -> HSTRING {
+    match NAME.get() {
+        Some(name) => HSTRING::from(format!(r"\\.\pipe\hotcorners-{name}")),
+        None => HSTRING::from(r"\\.\pipe\hotcorners"),
+    }
+}
+
+/// Name of the mutex only one instance can hold
+fn mutex_name() -> HSTRING {
+    match NAME.get() {
+        Some(name) => HSTRING::from(format!(r"Global\hotcorners_single_instance_{name}")),
+        None => HSTRING::from(r"Global\hotcorners_single_instance"),
+    }
+}
+
+/// Claims the single-instance mutex, returning `None` if another instance holds it
+pub fn acquire() -> windows::core::Result<Option<InstanceGuard>> {
+    unsafe {
+        let mutex = CreateMutexW(None, false, &mutex_name())?;
+        if GetLastError() == ERROR_ALREADY_EXISTS {
+            let _ = CloseHandle(mutex);
+            return Ok(None);
+        }
+        Ok(Some(InstanceGuard(mutex)))
+    }
+}
+
+/// Like `acquire`, but waits up to `REPLACE_TIMEOUT` for the running instance to exit, after it
+/// was told to
+pub fn acquire_after_shutdown() -> windows::core::Result<Option<InstanceGuard>> {
+    let mut waited = Duration::ZERO;
+    loop {
+        if let Some(guard) = acquire()? {
+            return Ok(Some(guard));
+        }
+        if waited >= REPLACE_TIMEOUT {
+            return Ok(None);
+        }
+        thread::sleep(REPLACE_POLL_INTERVAL);
+        waited += REPLACE_POLL_INTERVAL;
+    }
+}
@@ -0,0 +1,383 @@
+//! Named pipe server that lets other processes control the running instance.
+//!
+//! Every connection sends a single JSON request, e.g. `{"cmd":"disable","corner":"top-left"}`,
+//! and receives a single JSON response.
+
+use std::{
+    fmt,
+    sync::atomic::{AtomicBool, Ordering},
+    thread,
+};
+
+use serde::{Deserialize, Serialize};
+use tracing::error;
+use windows::Win32::{
+    Foundation::{
+        CloseHandle, ERROR_FILE_NOT_FOUND, ERROR_MORE_DATA, ERROR_PIPE_BUSY, ERROR_PIPE_CONNECTED,
+        GENERIC_READ, GENERIC_WRITE, HANDLE, HWND, LPARAM, WPARAM,
+    },
+    Storage::FileSystem::{
+        CreateFileW, FlushFileBuffers, ReadFile, WriteFile, FILE_FLAGS_AND_ATTRIBUTES,
+        FILE_SHARE_NONE, OPEN_EXISTING, PIPE_ACCESS_DUPLEX,
+    },
+    System::Pipes::{
+        ConnectNamedPipe, CreateNamedPipeW, DisconnectNamedPipe, SetNamedPipeHandleState,
+        WaitNamedPipeW, PIPE_READMODE_MESSAGE, PIPE_REJECT_REMOTE_CLIENTS, PIPE_TYPE_MESSAGE,
+        PIPE_WAIT,
+    },
+    UI::WindowsAndMessaging::PostMessageW,
+};
+
+use hotcorners_core::corners::Corner;
+
+use crate::{
+    instance, tray,
+    window::{SendHwnd, WM_SHUTDOWN},
+};
+
+/// Size of the pipe buffers. Requests have to fit, longer responses are read in parts.
+const BUFFER_SIZE: u32 = 4096;
+
+/// How long a client waits for the server to become available, in milliseconds
+const CLIENT_TIMEOUT_MS: u32 = 2000;
+
+/// Set in the exit path to stop the server thread
+static SHUTDOWN: AtomicBool = AtomicBool::new(false);
+
+/// A command sent to the running instance
+#[derive(Debug, Clone, Copy, Deserialize, Serialize)]
+#[serde(tag = "cmd", rename_all = "kebab-case", deny_unknown_fields)]
+pub enum Request {
+    /// Reload `config.toml`
+    Reload,
+    /// Report the active corners
+    Status,
+    /// Stop a corner from activating until it is enabled again
+    Disable {
+        /// Corner to disable on every monitor
+        corner: Corner,
+    },
+    /// Undo `Disable`
+    Enable {
+        /// Corner to enable on every monitor
+        corner: Corner,
+    },
+    /// Exit, e.g. to make way for a new instance
+    Shutdown,
+}
+
+/// The reply to a `Request`
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Response {
+    /// Whether the request succeeded
+    pub ok: bool,
+    /// What was done, if the request succeeded
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    /// What went wrong, if the request failed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Every corner zone, only included in the response to `Status`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub corners: Option<Vec<CornerStatus>>,
+}
+
+/// Why a command couldn't be sent to the running instance
+#[derive(Debug)]
+pub enum ClientError {
+    /// No instance is listening on the pipe
+    NotRunning,
+    /// Talking to the instance failed
+    Pipe(windows::core::Error),
+    /// The instance sent something other than a `Response`
+    InvalidResponse(serde_json::Error),
+}
+
+/// State of a single corner zone, part of the response to `Status`
+#[derive(Debug, Deserialize, Serialize)]
+pub struct CornerStatus {
+    /// Which corner of its monitor the zone is in
+    pub corner: Corner,
+    /// Whether the corner can activate, i.e. it wasn't disabled and hotcorners isn't paused
+    pub enabled: bool,
+    /// Hot rectangle in screen coordinates, as `[left, top, right, bottom]`
+    pub rect: [i32; 4],
+    /// How many times the corner activated since the configuration was last loaded
+    pub activations: u64,
+    /// How many of those activations' actions succeeded
+    pub succeeded: u64,
+    /// How many of them failed
+    pub failed: u64,
+}
+
+impl Response {
+    /// A successful response with `message`
+    fn ok(message: impl Into<String>) -> Self {
+        Self {
+            ok: true,
+            message: Some(message.into()),
+            error: None,
+            corners: None,
+        }
+    }
+
+    /// A failed response with `error`
+    fn error(error: impl Into<String>) -> Self {
+        Self {
+            ok: false,
+            message: None,
+            error: Some(error.into()),
+            corners: None,
+        }
+    }
+}
+
+impl fmt::Display for ClientError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::NotRunning => f.write_str("hotcorners isn't running"),
+            Self::Pipe(e) => write!(f, "Failed to talk to hotcorners: {e}"),
+            Self::InvalidResponse(e) => write!(f, "Invalid response from hotcorners: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for ClientError {}
+
+/// Sends `cmd`, a JSON encoded `Request`, to the running instance and returns its response
+pub fn connect_and_command(cmd: &str) -> Result<Response, ClientError> {
+    let pipe = open_pipe()?;
+    let response = exchange(pipe, cmd.as_bytes());
+    unsafe {
+        let _ = CloseHandle(pipe);
+    }
+
+    serde_json::from_slice(&response.map_err(ClientError::Pipe)?)
+        .map_err(ClientError::InvalidResponse)
+}
+
+/// Connects to the server in message mode, waiting up to `CLIENT_TIMEOUT_MS` if it is busy
+/// with another client
+fn open_pipe() -> Result<HANDLE, ClientError> {
+    let name = instance::pipe_name();
+    let open = || unsafe {
+        CreateFileW(
+            &name,
+            (GENERIC_READ | GENERIC_WRITE).0,
+            FILE_SHARE_NONE,
+            None,
+            OPEN_EXISTING,
+            FILE_FLAGS_AND_ATTRIBUTES(0),
+            None,
+        )
+    };
+    let pipe = match open() {
+        Err(e) if e.code() == ERROR_PIPE_BUSY.to_hresult() => {
+            if !unsafe { WaitNamedPipeW(&name, CLIENT_TIMEOUT_MS) }.as_bool() {
+                return Err(ClientError::Pipe(windows::core::Error::from_win32()));
+            }
+            open()
+        }
+        result => result,
+    };
+    let pipe = pipe.map_err(|e| {
+        if e.code() == ERROR_FILE_NOT_FOUND.to_hresult() {
+            ClientError::NotRunning
+        } else {
+            ClientError::Pipe(e)
+        }
+    })?;
+
+    if let Err(e) =
+        unsafe { SetNamedPipeHandleState(pipe, Some(&PIPE_READMODE_MESSAGE), None, None) }
+    {
+        unsafe {
+            let _ = CloseHandle(pipe);
+        }
+        return Err(ClientError::Pipe(e));
+    }
+    Ok(pipe)
+}
+
+/// Writes `request` to `pipe` and reads the whole response, however many reads it takes
+fn exchange(pipe: HANDLE, request: &[u8]) -> windows::core::Result<Vec<u8>> {
+    unsafe { WriteFile(pipe, Some(request), None, None)? };
+
+    let mut response = Vec::new();
+    let mut buffer = [0u8; BUFFER_SIZE as usize];
+    loop {
+        let mut len = 0u32;
+        let read = unsafe { ReadFile(pipe, Some(&mut buffer), Some(&raw mut len), None) };
+        response.extend_from_slice(&buffer[..len as usize]);
+        match read {
+            Ok(()) => return Ok(response),
+            // The rest of the message is left for the next read
+            Err(e) if e.code() == ERROR_MORE_DATA.to_hresult() => {}
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Starts the server on a background thread. Commands that change the corners update the tray
+/// icon of `hwnd`.
+pub fn serve(hwnd: HWND) {
+    let hwnd = SendHwnd::new(hwnd);
+    thread::spawn(move || {
+        let name = instance::pipe_name();
+        while !SHUTDOWN.load(Ordering::Acquire) {
+            let pipe = unsafe {
+                CreateNamedPipeW(
+                    &name,
+                    PIPE_ACCESS_DUPLEX,
+                    PIPE_TYPE_MESSAGE
+                        | PIPE_READMODE_MESSAGE
+                        | PIPE_WAIT
+                        | PIPE_REJECT_REMOTE_CLIENTS,
+                    1,
+                    BUFFER_SIZE,
+                    BUFFER_SIZE,
+                    0,
+                    None,
+                )
+            };
+            if pipe.is_invalid() {
+                error!(
+                    "Failed to create the control pipe: {}",
+                    windows::core::Error::from_win32()
+                );
+                return;
+            }
+
+            if wait_for_client(pipe) && !SHUTDOWN.load(Ordering::Acquire) {
+                handle_client(pipe, hwnd.get());
+            }
+
+            unsafe {
+                let _ = DisconnectNamedPipe(pipe);
+                let _ = CloseHandle(pipe);
+            }
+        }
+    });
+}
+
+/// Stops the server thread once it is done with the current client
+pub fn shut_down() {
+    SHUTDOWN.store(true, Ordering::Release);
+
+    // Connect once to wake the server thread up if it is waiting for a client
+    unsafe {
+        if let Ok(pipe) = CreateFileW(
+            &instance::pipe_name(),
+            (GENERIC_READ | GENERIC_WRITE).0,
+            FILE_SHARE_NONE,
+            None,
+            OPEN_EXISTING,
+            FILE_FLAGS_AND_ATTRIBUTES(0),
+            None,
+        ) {
+            let _ = CloseHandle(pipe);
+        }
+    }
+}
+
+/// Blocks until a client connects to `pipe`, returning `false` if that failed
+fn wait_for_client(pipe: HANDLE) -> bool {
+    match unsafe { ConnectNamedPipe(pipe, None) } {
+        Ok(()) => true,
+        // The client connected between `CreateNamedPipeW` and `ConnectNamedPipe`
+        Err(e) => e.code() == ERROR_PIPE_CONNECTED.to_hresult(),
+    }
+}
+
+/// Reads a request from the client connected to `pipe` and writes the response
+fn handle_client(pipe: HANDLE, hwnd: HWND) {
+    let mut buffer = [0u8; BUFFER_SIZE as usize];
+    let mut len = 0u32;
+    let read = unsafe { ReadFile(pipe, Some(&mut buffer), Some(&raw mut len), None) };
+
+    let response = match read {
+        Ok(()) => match serde_json::from_slice(&buffer[..len as usize]) {
+            Ok(request) => handle_request(request, hwnd),
+            Err(e) => Response::error(format!("Invalid request: {e}")),
+        },
+        Err(e) if e.code() == ERROR_MORE_DATA.to_hresult() => Response::error("Request too long"),
+        Err(e) => {
+            error!("Failed to read from the control pipe: {e}");
+            return;
+        }
+    };
+
+    let Ok(response) = serde_json::to_vec(&response) else {
+        return;
+    };
+    unsafe {
+        if let Err(e) = WriteFile(pipe, Some(&response), None, None) {
+            error!("Failed to write to the control pipe: {e}");
+        }
+        let _ = FlushFileBuffers(pipe);
+    }
+}
+
+/// Runs `request`, updating the tray icon of `hwnd` if needed
+fn handle_request(request: Request, hwnd: HWND) -> Response {
+    match request {
+        Request::Reload => {
+            let result = crate::reload_config();
+            tray::update_tooltip(hwnd);
+            match result {
+                Ok(()) => Response::ok("Configuration reloaded"),
+                Err(e) => Response::error(e),
+            }
+        }
+        Request::Status => status(),
+        Request::Disable { corner } => {
+            crate::DISABLED.fetch_or(corner.mask(), Ordering::Relaxed);
+            tray::update_tooltip(hwnd);
+            Response::ok(format!("Disabled {corner}"))
+        }
+        Request::Enable { corner } => {
+            crate::DISABLED.fetch_and(!corner.mask(), Ordering::Relaxed);
+            tray::update_tooltip(hwnd);
+            Response::ok(format!("Enabled {corner}"))
+        }
+        Request::Shutdown => match unsafe { PostMessageW(hwnd, WM_SHUTDOWN, WPARAM(0), LPARAM(0)) }
+        {
+            Ok(()) => Response::ok("Shutting down"),
+            Err(e) => Response::error(format!("Failed to shut down: {e}")),
+        },
+    }
+}
+
+/// Describes every corner zone
+fn status() -> Response {
+    let paused = crate::PAUSED.load(Ordering::Relaxed);
+    let disabled = crate::DISABLED.load(Ordering::Relaxed);
+    let corners: Vec<CornerStatus> = crate::CORNERS
+        .read()
+        .unwrap()
+        .iter()
+        .map(|state| CornerStatus {
+            corner: state.corner,
+            enabled: !paused && disabled & state.corner.mask() == 0,
+            rect: [
+                state.rect.left,
+                state.rect.top,
+                state.rect.right,
+                state.rect.bottom,
+            ],
+            activations: state.activations(),
+            succeeded: state.action_results.succeeded(),
+            failed: state.action_results.failed(),
+        })
+        .collect();
+
+    let active = crate::active_corners();
+    Response {
+        corners: Some(corners),
+        ..Response::ok(format!(
+            "{active} corner{} active{}",
+            if active == 1 { "" } else { "s" },
+            if paused { ", paused" } else { "" }
+        ))
+    }
+}
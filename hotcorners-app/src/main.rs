@@ -0,0 +1,768 @@
+//! A simple hot corners implementation for Windows 10/11
+#![cfg(windows)]
+#![windows_subsystem = "windows"]
+
+mod autostart;
+mod cli;
+mod diagnose;
+mod instance;
+mod ipc;
+mod service;
+#[cfg_attr(not(feature = "tray-icon"), path = "no_tray.rs")]
+mod tray;
+mod watch;
+mod window;
+
+use std::{
+    cell::RefCell,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, AtomicU8, AtomicUsize, Ordering},
+        Arc, LazyLock, PoisonError, RwLock,
+    },
+    thread,
+    time::Duration,
+};
+
+use tracing::{debug, error, info, trace, warn};
+use tracing_subscriber::EnvFilter;
+use windows::{
+    core::Result,
+    Win32::{
+        Foundation::{HINSTANCE, HWND, LPARAM, LRESULT, RECT, WPARAM},
+        System::LibraryLoader::GetModuleHandleW,
+        UI::{
+            HiDpi::{SetProcessDpiAwarenessContext, DPI_AWARENESS_CONTEXT_PER_MONITOR_AWARE_V2},
+            Input::KeyboardAndMouse::{
+                GetKeyboardState, SendInput, HOT_KEY_MODIFIERS, INPUT, INPUT_0, INPUT_MOUSE,
+                MOD_ALT, MOD_CONTROL, MOD_SHIFT, MOUSEEVENTF_MOVE, MOUSEINPUT, VIRTUAL_KEY, VK_C,
+                VK_CONTROL, VK_LBUTTON, VK_LWIN, VK_MBUTTON, VK_MENU, VK_Q, VK_RBUTTON, VK_RWIN,
+                VK_SHIFT, VK_XBUTTON1, VK_XBUTTON2,
+            },
+            Shell::SHQueryUserNotificationState,
+            WindowsAndMessaging::{
+                CallNextHookEx, DispatchMessageW, GetMessageW, GetSystemMetrics, SetWindowsHookExW,
+                HHOOK, MSG, MSLLHOOKSTRUCT, SM_REMOTESESSION, WH_MOUSE_LL, WM_HOTKEY, WM_MOUSEMOVE,
+            },
+        },
+    },
+};
+
+#[cfg(feature = "plugins")]
+use hotcorners_core::plugins;
+use hotcorners_core::{
+    actions, activation_log, config,
+    config::{ActionType, Config},
+    corners::{self, rebuild_corner_zones, CornerState},
+    etw,
+    guard::HookGuard,
+    hotkeys::{HotkeyAction, HotkeyRegistry},
+    input, power, suppress,
+    worker::{self, spawn_corner},
+};
+
+/// Base key for exiting, unless `exit_hotkey` is configured
+const EXIT_HOTKEY: VIRTUAL_KEY = VK_C;
+/// Modifier key(s) for exiting, unless `exit_hotkey` is configured
+const EXIT_HOTKEY_MODIFIERS: HOT_KEY_MODIFIERS = HOT_KEY_MODIFIERS(MOD_CONTROL.0 | MOD_ALT.0);
+
+/// Mouse buttons that stop a corner from activating while held, e.g. while dragging a window
+const MOUSE_BUTTONS: [VIRTUAL_KEY; 5] =
+    [VK_LBUTTON, VK_RBUTTON, VK_MBUTTON, VK_XBUTTON1, VK_XBUTTON2];
+
+/// Modifier keys that stop a corner from activating while held
+const MODIFIER_KEYS: [VIRTUAL_KEY; 5] = [VK_SHIFT, VK_CONTROL, VK_MENU, VK_LWIN, VK_RWIN];
+
+/// Tried in order when `auto_fallback_hotkey` is set and another program uses the exit hotkey
+const EXIT_HOTKEY_FALLBACKS: [(HOT_KEY_MODIFIERS, VIRTUAL_KEY); 2] = [
+    (EXIT_HOTKEY_MODIFIERS, VK_Q),
+    (
+        HOT_KEY_MODIFIERS(MOD_CONTROL.0 | MOD_ALT.0 | MOD_SHIFT.0),
+        EXIT_HOTKEY,
+    ),
+];
+
+/// Exit code when another instance is already running
+const EXIT_ALREADY_RUNNING: i32 = 3;
+
+/// How long to wait for the mouse hook to see the synthetic event sent by `verify_hook`
+const HOOK_VERIFY_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Currently active corners, replaced wholesale when the configuration is reloaded
+static CORNERS: RwLock<Vec<Arc<CornerState>>> = RwLock::new(Vec::new());
+
+/// Configuration the current corners were built from
+static CONFIG: LazyLock<RwLock<Config>> = LazyLock::new(RwLock::default);
+
+thread_local! {
+    /// The low-level mouse hook, installed by the thread running the message loop
+    static MOUSE_HOOK: RefCell<Option<HookGuard>> = const { RefCell::new(None) };
+
+    /// Hotkeys registered by the thread running the message loop, they are tied to that thread
+    static HOTKEYS: RefCell<HotkeyRegistry> = RefCell::default();
+}
+
+/// Set by `mouse_hook_callback` the first time it runs
+static HOOK_CALLED: AtomicBool = AtomicBool::new(false);
+
+/// Cursor position of the last mouse move the hook processed, packed with `pack_point`. Starts out
+/// as a position the cursor is unlikely to be at, `(-1, -1)`.
+///
+/// Only the hook thread touches it, it is atomic in case that ever changes.
+static LAST_POINT: AtomicU64 = AtomicU64::new(u64::MAX);
+
+/// Index of the active profile in `Config::profiles`
+static PROFILE: AtomicUsize = AtomicUsize::new(0);
+
+/// Set while every corner is paused from the tray menu
+static PAUSED: AtomicBool = AtomicBool::new(false);
+
+/// Set while the session is locked, no corner activates then
+static SESSION_LOCKED: AtomicBool = AtomicBool::new(false);
+
+/// Set while hotcorners runs in a Remote Desktop session
+static REMOTE_SESSION: AtomicBool = AtomicBool::new(false);
+
+/// Corners disabled through the control pipe, one bit per corner as given by `Corner::mask`. Kept
+/// across configuration reloads.
+static DISABLED: AtomicU8 = AtomicU8::new(0);
+
+fn main() -> Result<()> {
+    let args = cli::parse(std::env::args().skip(1)).unwrap_or_else(|e| {
+        cli::attach_console();
+        println!("{e}");
+        std::process::exit(1);
+    });
+    if let Some(name) = &args.instance_name {
+        instance::set_name(name.clone());
+    }
+    if let Some(path) = &args.config {
+        let result = config::set_config_path(path).and_then(|()| {
+            // `--init` creates the file, but running requires it to exist
+            if args.command == cli::Command::Run && !path.is_file() {
+                return Err(format!("Config file {} doesn't exist", path.display()));
+            }
+            Ok(())
+        });
+        if let Err(e) = result {
+            cli::attach_console();
+            println!("{e}");
+            std::process::exit(1);
+        }
+    }
+    if args.command != cli::Command::Run {
+        std::process::exit(cli::run(&args));
+    }
+    if args.debug || args.simulate {
+        cli::attach_console();
+    }
+    init_logging(args.debug);
+    etw::register();
+    // Released when the process exits
+    let _instance = claim_instance(args.replace);
+
+    // Has to happen before any monitor is queried, otherwise Windows reports scaled coordinates
+    // and `GetDpiForMonitor` returns the system DPI
+    unsafe {
+        if let Err(e) = SetProcessDpiAwarenessContext(DPI_AWARENESS_CONTEXT_PER_MONITOR_AWARE_V2) {
+            warn!("Failed to enable per-monitor DPI awareness: {e}");
+        }
+    }
+
+    let config = config::load().unwrap_or_else(|e| exit_with_error(&e));
+    #[cfg(feature = "plugins")]
+    plugins::load(&config);
+    if let Err(e) = apply_config(&config, config.default_profile()) {
+        exit_with_error(&e);
+    }
+
+    update_remote_session();
+    if REMOTE_SESSION.load(Ordering::Relaxed) && !config.suppress_in_remote_desktop {
+        warn!("Running in a Remote Desktop session, corners act on the remote machine");
+    }
+
+    let hwnd = window::create()?;
+    window::register_session_notifications(hwnd);
+    tray::add(hwnd)?;
+    let tray_hwnd = window::SendHwnd::new(hwnd);
+    worker::set_notify_handler(move |message, failed| {
+        if !failed || CONFIG.read().unwrap().notify_on_action_error {
+            tray::notify(tray_hwnd.get(), message, failed);
+        }
+    });
+    if let Some(path) = config::resolve_config_path() {
+        watch::watch(&path, hwnd);
+    }
+    ipc::serve(hwnd);
+    power::watch();
+
+    MOUSE_HOOK.set(Some(install_mouse_hook()?));
+    verify_hook(hwnd);
+    register_hotkeys(&config);
+    if args.simulate {
+        worker::SIMULATE_MODE.store(true, Ordering::Relaxed);
+        check_simulated_actions(&config);
+        window::start_simulation_timer(hwnd);
+        println!("Simulating for 30 seconds, corners only print what they would do");
+    }
+
+    let reason = run_message_loop(hwnd);
+    info!(?reason, "Exiting");
+    if args.simulate {
+        print_simulation_summary();
+    }
+    cleanup(hwnd);
+
+    match reason {
+        ExitReason::Error(e) => Err(e),
+        ExitReason::Hotkey | ExitReason::Quit => Ok(()),
+    }
+}
+
+/// Logs `error` and prints it to the console hotcorners was started from, if any, then exits
+fn exit_with_error(error: &dyn std::fmt::Display) -> ! {
+    error!("{error}");
+    cli::attach_console();
+    eprintln!("{error}");
+    std::process::exit(1);
+}
+
+/// Logs to stderr, at the level given by `RUST_LOG` or `warn` if it isn't set. `debug` logs
+/// everything instead.
+fn init_logging(debug: bool) {
+    let filter = if debug {
+        EnvFilter::new("trace")
+    } else {
+        EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("warn"))
+    };
+    tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(std::io::stderr)
+        .init();
+}
+
+/// Reports the problems `--simulate` can find without running the actions: programs that don't
+/// exist and actions this build can't perform
+fn check_simulated_actions(config: &Config) {
+    for corner in config.all_corners() {
+        if let ActionType::Exec { path, .. } = &corner.action {
+            if !actions::program_exists(path) {
+                println!(
+                    "The {} corner would fail, {} doesn't exist",
+                    corner.position,
+                    path.display()
+                );
+            }
+        }
+        if let Some(feature) = corner.action.missing_feature() {
+            println!(
+                "The {} corner would do nothing, {} needs the {feature} feature",
+                corner.position,
+                corner.action.name()
+            );
+        }
+    }
+}
+
+/// Prints how often each corner would have activated during `--simulate`
+fn print_simulation_summary() {
+    println!("Simulated activations:");
+    for state in CORNERS.read().unwrap().iter() {
+        let RECT {
+            left,
+            top,
+            right,
+            bottom,
+        } = state.rect;
+        println!(
+            "  {} ({left}, {top})-({right}, {bottom}): {}",
+            state.corner,
+            state.activations()
+        );
+    }
+}
+
+/// Makes sure no other instance with the same name runs, telling it to exit first if `replace`
+/// is set. Exits the process if another instance keeps running.
+fn claim_instance(replace: bool) -> instance::InstanceGuard {
+    let result = instance::acquire().and_then(|guard| match guard {
+        None if replace => {
+            let cmd = serde_json::to_string(&ipc::Request::Shutdown)
+                .expect("requests can always be serialized");
+            if let Err(e) = ipc::connect_and_command(&cmd) {
+                warn!("Failed to stop the running instance: {e}");
+            }
+            instance::acquire_after_shutdown()
+        }
+        guard => Ok(guard),
+    });
+
+    match result {
+        Ok(Some(guard)) => guard,
+        Ok(None) => {
+            cli::attach_console();
+            println!("hotcorners is already running");
+            std::process::exit(EXIT_ALREADY_RUNNING);
+        }
+        Err(e) => {
+            cli::attach_console();
+            println!("Failed to check for a running instance: {e}");
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Why `run_message_loop` returned
+#[derive(Debug)]
+enum ExitReason {
+    /// The exit hotkey was pressed
+    Hotkey,
+    /// `WM_QUIT` was posted, e.g. from the tray menu
+    Quit,
+    /// Retrieving a message failed
+    Error(windows::core::Error),
+}
+
+/// Installs the low-level mouse hook, which stays installed until the returned guard is dropped.
+///
+/// The hook runs on the calling thread, so that thread must run the message loop.
+fn install_mouse_hook() -> Result<HookGuard> {
+    // Some Windows configurations drop low-level hooks installed without a module handle
+    let hook = unsafe {
+        let instance: HINSTANCE = GetModuleHandleW(None)?.into();
+        SetWindowsHookExW(WH_MOUSE_LL, Some(mouse_hook_callback), instance, 0)?
+    };
+    etw::hook_installed();
+    Ok(HookGuard(hook))
+}
+
+/// Dispatches messages until hotcorners should exit. Hotkey messages, which are posted to the
+/// thread rather than to `hwnd`, are handled here.
+///
+/// # Safety
+///
+/// Has to run on the thread that created `hwnd`, installed the mouse hook and registered the
+/// hotkeys, since all of them deliver their messages to that thread.
+fn run_message_loop(hwnd: HWND) -> ExitReason {
+    let mut msg = MSG::default();
+    loop {
+        match unsafe { GetMessageW(&raw mut msg, HWND::default(), 0, 0) }.0 {
+            -1 => return ExitReason::Error(windows::core::Error::from_win32()),
+            0 => return ExitReason::Quit,
+            _ => {}
+        }
+
+        if msg.message == WM_HOTKEY {
+            if dispatch_hotkey(msg.wParam, hwnd) {
+                return ExitReason::Hotkey;
+            }
+            continue;
+        }
+
+        unsafe {
+            DispatchMessageW(&raw const msg);
+        }
+    }
+}
+
+/// Stops the control pipe and the corner threads, removes the tray icon of `hwnd`, the hotkeys
+/// and the mouse hook. Running it again does nothing.
+///
+/// # Safety
+///
+/// Has to run on the thread that ran the message loop, hotkeys can only be unregistered by the
+/// thread that registered them.
+fn cleanup(hwnd: HWND) {
+    ipc::shut_down();
+    window::unregister_session_notifications(hwnd);
+    tray::remove(hwnd);
+    HOTKEYS.with_borrow_mut(HotkeyRegistry::unregister_all);
+    MOUSE_HOOK.take();
+    let corners = std::mem::take(&mut *CORNERS.write().unwrap());
+    for state in &corners {
+        state.worker.shut_down();
+    }
+    for state in &corners {
+        state.worker.join();
+    }
+    etw::unregister();
+}
+
+/// (Re)registers the exit hotkey, and the profile hotkey if `config` has one. Hotcorners can
+/// still be exited from the tray menu if the exit hotkey can't be registered.
+fn register_hotkeys(config: &Config) {
+    HOTKEYS.with_borrow_mut(|hotkeys| {
+        hotkeys.unregister_all();
+
+        // Both hotkeys were already validated while loading the config
+        let (key, modifiers) = config
+            .exit_hotkey
+            .as_ref()
+            .and_then(|hotkey| config::parse_hotkey(hotkey).ok())
+            .unwrap_or((EXIT_HOTKEY, EXIT_HOTKEY_MODIFIERS));
+        let fallbacks: &[_] = if config.auto_fallback_hotkey {
+            &EXIT_HOTKEY_FALLBACKS
+        } else {
+            &[]
+        };
+        if let Err(e) = hotkeys.register(modifiers, key, fallbacks, HotkeyAction::Exit) {
+            error!("Failed to register the exit hotkey, exit from the tray menu instead: {e}");
+        }
+
+        let profile_hotkey = config
+            .profile_cycle_hotkey
+            .as_ref()
+            .and_then(|hotkey| config::parse_hotkey(hotkey).ok());
+        if let Some((key, modifiers)) = profile_hotkey {
+            if let Err(e) = hotkeys.register(modifiers, key, &[], HotkeyAction::CycleProfile) {
+                error!("Failed to register the profile hotkey: {e}");
+            }
+        }
+    });
+}
+
+/// Runs the action of the hotkey with ID `id`, updating the tray icon of `hwnd` if needed.
+/// Returns `true` if hotcorners should exit.
+fn dispatch_hotkey(id: WPARAM, hwnd: HWND) -> bool {
+    let action = i32::try_from(id.0)
+        .ok()
+        .and_then(|id| HOTKEYS.with_borrow(|hotkeys| hotkeys.action(id)));
+    match action {
+        Some(HotkeyAction::Exit) => true,
+        Some(HotkeyAction::CycleProfile) => {
+            tray::cycle_profile(hwnd);
+            false
+        }
+        None => false,
+    }
+}
+
+/// Installs the mouse hook and registers the hotkeys again. Low-level hooks can silently stop
+/// working while the system sleeps or hibernates, so this runs after every resume. Has to be
+/// called on the thread running the message loop.
+fn reinstall_hooks() {
+    // The old hook is removed first so the two never run at the same time
+    MOUSE_HOOK.take();
+    match install_mouse_hook() {
+        Ok(hook) => {
+            MOUSE_HOOK.set(Some(hook));
+            info!("Reinstalled the mouse hook after resume");
+        }
+        Err(e) => error!("Failed to reinstall the mouse hook: {e}"),
+    }
+
+    register_hotkeys(&CONFIG.read().unwrap());
+    info!("Registered the hotkeys again after resume");
+}
+
+/// Checks whether hotcorners runs in a Remote Desktop session, e.g. after a client connected
+fn update_remote_session() {
+    let remote = unsafe { GetSystemMetrics(SM_REMOTESESSION) } != 0;
+    REMOTE_SESSION.store(remote, Ordering::Relaxed);
+}
+
+/// Builds the corners of profile `profile` in `config` and starts their worker threads,
+/// replacing the currently active corners. Nothing changes if the configuration can't be applied.
+fn apply_config(config: &Config, profile: usize) -> std::result::Result<(), String> {
+    let corners = rebuild_corner_zones(config, profile)?
+        .into_iter()
+        .map(|zone| spawn_corner(&zone))
+        .collect();
+
+    activation_log::configure(
+        config.log_file.as_deref(),
+        config.max_log_size_mb.saturating_mul(1024 * 1024),
+    );
+    *CONFIG.write().unwrap() = config.clone();
+    PROFILE.store(profile, Ordering::Relaxed);
+    let old = std::mem::replace(&mut *CORNERS.write().unwrap(), corners);
+    // Not joined, that would stall the mouse hook until running actions finish. The zone may be
+    // gone, e.g. because its monitor was removed.
+    for state in old {
+        state.worker.shut_down();
+    }
+
+    Ok(())
+}
+
+/// Rebuilds the corners for the current monitor layout, e.g. after a monitor was added
+fn rebuild_corners() {
+    let config = CONFIG.read().unwrap().clone();
+    if let Err(e) = apply_config(&config, PROFILE.load(Ordering::Relaxed)) {
+        error!("{e}");
+    }
+}
+
+/// Reloads `config.toml`, keeping the current corners if it can't be loaded. The active profile
+/// stays active if it still exists.
+fn reload_config() -> std::result::Result<(), String> {
+    let active = active_profile();
+    let config = config::load().map_err(|e| e.to_string())?;
+    #[cfg(feature = "plugins")]
+    plugins::load(&config);
+    let profile = active
+        .and_then(|name| config.profile_index(&name))
+        .unwrap_or_else(|| config.default_profile());
+    apply_config(&config, profile)
+}
+
+/// Switches to the next profile, wrapping around after the last one, and returns its name
+fn cycle_profile() -> std::result::Result<String, String> {
+    let config = CONFIG.read().unwrap().clone();
+    if config.profiles.is_empty() {
+        return Err("No profiles configured".to_owned());
+    }
+
+    let profile = (PROFILE.load(Ordering::Relaxed) + 1) % config.profiles.len();
+    apply_config(&config, profile)?;
+    Ok(config.profiles[profile].name.clone())
+}
+
+/// Name of the active profile, if there are profiles
+fn active_profile() -> Option<String> {
+    CONFIG
+        .read()
+        .unwrap()
+        .profile_name(PROFILE.load(Ordering::Relaxed))
+        .map(str::to_owned)
+}
+
+/// Number of corners that can currently activate
+fn active_corners() -> usize {
+    if PAUSED.load(Ordering::Relaxed) {
+        return 0;
+    }
+
+    let disabled = DISABLED.load(Ordering::Relaxed);
+    CORNERS
+        .read()
+        .unwrap()
+        .iter()
+        .filter(|state| disabled & state.corner.mask() == 0)
+        .count()
+}
+
+/// Checks in the background that the mouse hook actually receives events, warning through the
+/// tray icon of `hwnd` if it doesn't.
+///
+/// Sends a mouse move of zero pixels, so the cursor stays where it is. The hook only runs while the
+/// thread that installed it pumps messages, so this has to be called before entering the message
+/// loop.
+fn verify_hook(hwnd: HWND) {
+    let hwnd = window::SendHwnd::new(hwnd);
+    thread::spawn(move || {
+        let input = INPUT {
+            r#type: INPUT_MOUSE,
+            Anonymous: INPUT_0 {
+                mi: MOUSEINPUT {
+                    dwFlags: MOUSEEVENTF_MOVE,
+                    dwExtraInfo: input::HOTCORNERS_EXTRA_INFO,
+                    ..Default::default()
+                },
+            },
+        };
+        // `size_of::<INPUT>()` will never > i32::MAX
+        #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+        let sent = unsafe { SendInput(&[input], std::mem::size_of::<INPUT>() as i32) };
+        if sent != 1 {
+            // Input is blocked, e.g. by UIPI, so there is nothing to verify
+            return;
+        }
+
+        let start = std::time::Instant::now();
+        while !HOOK_CALLED.load(Ordering::Relaxed) {
+            if start.elapsed() > HOOK_VERIFY_TIMEOUT {
+                let message = "The mouse hook isn't receiving events, corners won't activate";
+                error!("{message}");
+                tray::notify(hwnd.get(), message, true);
+                return;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        debug!("Mouse hook verified");
+    });
+}
+
+/// Callback that is registered with Windows in order to start the hot corner activation.
+///
+/// Never inlined, since the optimizer must not merge or eliminate the function whose address is
+/// passed to `SetWindowsHookExW`.
+#[inline(never)]
+extern "system" fn mouse_hook_callback(n_code: i32, w_param: WPARAM, l_param: LPARAM) -> LRESULT {
+    HOOK_CALLED.store(true, Ordering::Relaxed);
+    unsafe {
+        let evt = l_param.0 as *mut MSLLHOOKSTRUCT;
+
+        // If the mouse hasn't moved, we're done
+        let wm_evt = u32::try_from(w_param.0).expect("w_param.0 fits in a u32");
+        if wm_evt != WM_MOUSEMOVE {
+            return CallNextHookEx(HHOOK::default(), n_code, w_param, l_param);
+        }
+        // Touchpads in particular report moves that don't move the cursor
+        let point = corners::pack_point((*evt).pt);
+        if LAST_POINT.swap(point, Ordering::Relaxed) == point {
+            return CallNextHookEx(HHOOK::default(), n_code, w_param, l_param);
+        }
+        trace!(pt.x = (*evt).pt.x, pt.y = (*evt).pt.y, "WM_MOUSEMOVE");
+
+        if PAUSED.load(Ordering::Relaxed) || SESSION_LOCKED.load(Ordering::Relaxed) {
+            return CallNextHookEx(HHOOK::default(), n_code, w_param, l_param);
+        }
+
+        // A panic must not unwind out of the hook, and a thread that panicked while holding the
+        // lock left a config or corners that are still whole
+        let config = CONFIG.read().unwrap_or_else(PoisonError::into_inner);
+        if (config.disable_on_battery && power::on_battery())
+            || (config.suppress_in_remote_desktop && REMOTE_SESSION.load(Ordering::Relaxed))
+        {
+            return CallNextHookEx(HHOOK::default(), n_code, w_param, l_param);
+        }
+
+        let disabled = DISABLED.load(Ordering::Relaxed);
+        let touch = config.suppress_touch && input::is_touch_or_injected(&*evt);
+
+        let corners = CORNERS.read().unwrap_or_else(PoisonError::into_inner);
+        // Corners take priority over the edges they are on
+        let in_corner = corners.iter().any(|state| {
+            !state.corner.is_edge()
+                && disabled & state.corner.mask() == 0
+                && state.contains((*evt).pt)
+        });
+
+        for state in corners.iter() {
+            let event = ((*evt).pt, (*evt).time);
+            if disabled & state.corner.mask() != 0 {
+                continue;
+            }
+
+            // Check if the cursor is hot or cold
+            if !state.contains((*evt).pt) || (in_corner && state.corner.is_edge()) {
+                state.record_outside(event.0, event.1);
+                if state.still_hot.swap(false, Ordering::Relaxed) {
+                    debug!(corner = %state.corner, pt.x = (*evt).pt.x, pt.y = (*evt).pt.y, "Left corner");
+                    // Stop a worker that is still waiting out the delay
+                    state.worker.cancel_flag.store(true, Ordering::Release);
+                }
+                continue;
+            }
+
+            // Only logged once per entry, even if the checks below keep it from being handled
+            if state.record_inside() {
+                debug!(corner = %state.corner, pt.x = (*evt).pt.x, pt.y = (*evt).pt.y, "Entered corner");
+                etw::corner_entered(state.corner);
+            }
+
+            // The corner is hot, check if it was already hot
+            if state.still_hot.load(Ordering::Relaxed) {
+                continue;
+            }
+            let previous = state.last_outside();
+
+            // Swept through the corner too fast, ignore it until the cursor leaves again
+            if state.entered_too_fast(previous, event) {
+                state.still_hot.store(true, Ordering::Relaxed);
+                continue;
+            }
+
+            // Same for entries from the wrong side
+            if !state.entered_from_allowed_direction(previous.0, event.0) {
+                debug!(corner = %state.corner, "Entered from an ignored direction");
+                state.still_hot.store(true, Ordering::Relaxed);
+                continue;
+            }
+
+            // Same for swipes on a touchscreen
+            if touch {
+                debug!(corner = %state.corner, "Touch or injected input, ignored");
+                state.still_hot.store(true, Ordering::Relaxed);
+                continue;
+            }
+
+            // Don't fire again right after an activation
+            if state.cooling_down() {
+                continue;
+            }
+
+            // The key state is only read once the cursor is in a corner, never on every move
+            if activation_blocked() {
+                continue;
+            }
+
+            // Skip fullscreen games and videos. Like the key state above, only checked once the
+            // cursor is in a corner, since these calls are comparatively expensive and the hook
+            // can see a thousand events per second from a gaming mouse.
+            //
+            // If the notification state comes back as `BUSY`, we can usually assume there's a
+            // fullscreen app
+            if SHQueryUserNotificationState()
+                .is_ok_and(|notify_state| notify_state.0 == 3 || notify_state.0 == 2)
+            {
+                continue;
+            }
+            if config.suppress_fullscreen && suppress::foreground_is_fullscreen() {
+                continue;
+            }
+            if suppress::foreground_suppressed(&config.suppress_for_class, &config.suppress_for) {
+                continue;
+            }
+
+            // The corner is hot, and was previously cold. Notify the worker thread to resume.
+            if state.worker.arm() {
+                trace!(corner = %state.corner, "Unparked worker");
+            } else {
+                debug!(corner = %state.corner, "Worker is still busy, not armed again");
+            }
+
+            state.still_hot.store(true, Ordering::Relaxed);
+        }
+
+        CallNextHookEx(HHOOK::default(), n_code, w_param, l_param)
+    }
+}
+
+/// Checks whether a mouse button or modifier key is currently pressed, in which case a corner
+/// shouldn't activate
+fn activation_blocked() -> bool {
+    // One call for every key, mouse buttons included
+    let mut keystate = [0u8; 256];
+    if unsafe { GetKeyboardState(&mut keystate) }.is_err() {
+        return false;
+    }
+    trace!(
+        shift = keystate[VK_SHIFT.0 as usize],
+        ctrl = keystate[VK_CONTROL.0 as usize],
+        alt = keystate[VK_MENU.0 as usize],
+        lwin = keystate[VK_LWIN.0 as usize],
+        rwin = keystate[VK_RWIN.0 as usize],
+        "Modifier state"
+    );
+
+    MOUSE_BUTTONS
+        .iter()
+        .chain(&MODIFIER_KEYS)
+        .any(|key| keydown(keystate[key.0 as usize]))
+}
+
+/// Whether a key is pressed according to its `GetKeyboardState` entry, i.e. its high bit is set.
+/// The low bit is the toggle state of keys like Caps Lock and is ignored.
+fn keydown(key: u8) -> bool {
+    (key & 0x80) != 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keydown_checks_the_high_bit() {
+        assert!(keydown(0x80));
+        assert!(keydown(0xFF));
+        assert!(!keydown(0x7F));
+        assert!(!keydown(0x00));
+    }
+
+    #[test]
+    fn keydown_ignores_the_toggle_bit() {
+        assert!(!keydown(0x01));
+        assert!(keydown(0x81));
+    }
+}
@@ -0,0 +1,42 @@
+//! Stand-in for the tray icon when built without the `tray-icon` feature. Messages that would
+//! show up as balloon notifications are only logged.
+
+use tracing::{error, info};
+use windows::{core::Result, Win32::Foundation::HWND};
+
+/// Does nothing, there is no tray icon
+#[allow(clippy::unnecessary_wraps)]
+pub fn add(_hwnd: HWND) -> Result<()> {
+    Ok(())
+}
+
+/// Does nothing, there is no tray icon
+pub fn remove(_hwnd: HWND) {}
+
+/// Does nothing, there is no tray icon
+pub fn update_tooltip(_hwnd: HWND) {}
+
+/// Reloads `config.toml` and logs the outcome
+pub fn reload_config(hwnd: HWND) {
+    match crate::reload_config() {
+        Ok(()) => notify(hwnd, "Configuration reloaded", false),
+        Err(e) => notify(hwnd, &e, true),
+    }
+}
+
+/// Switches to the next profile and logs its name
+pub fn cycle_profile(hwnd: HWND) {
+    match crate::cycle_profile() {
+        Ok(name) => notify(hwnd, &format!("Switched to profile {name}"), false),
+        Err(e) => notify(hwnd, &e, true),
+    }
+}
+
+/// Logs what the tray icon would have shown
+pub fn notify(_hwnd: HWND, message: &str, error: bool) {
+    if error {
+        error!("{message}");
+    } else {
+        info!("{message}");
+    }
+}
@@ -0,0 +1,361 @@
+//! Running hotcorners as a Windows service, which `sc.exe` and Group Policy can manage, instead of
+//! starting it through the `Run` registry key.
+//!
+//! Services run in session 0, which can't see the mouse or the desktop of the user, so the service
+//! doesn't install the mouse hook itself. It starts a regular instance in the session of whoever
+//! is logged on at the console, and stops that instance through the control pipe when the service
+//! stops.
+
+use std::{
+    env,
+    ffi::{OsStr, OsString},
+    os::windows::ffi::OsStrExt,
+    path::PathBuf,
+    str::FromStr,
+    sync::mpsc,
+    time::Duration,
+};
+
+use tracing::{debug, error, warn};
+use windows::{
+    core::{w, PWSTR},
+    Win32::{
+        Foundation::{CloseHandle, HANDLE, WAIT_TIMEOUT},
+        System::{
+            Environment::{CreateEnvironmentBlock, DestroyEnvironmentBlock},
+            RemoteDesktop::{WTSGetActiveConsoleSessionId, WTSQueryUserToken},
+            Threading::{
+                CreateProcessAsUserW, TerminateProcess, WaitForSingleObject,
+                CREATE_UNICODE_ENVIRONMENT, PROCESS_INFORMATION, STARTUPINFOW,
+            },
+        },
+    },
+};
+use windows_service::{
+    define_windows_service,
+    service::{
+        ServiceAccess, ServiceControl, ServiceControlAccept, ServiceErrorControl, ServiceExitCode,
+        ServiceInfo, ServiceStartType, ServiceState, ServiceStatus, ServiceType,
+        SessionChangeReason,
+    },
+    service_control_handler::{self, ServiceControlHandlerResult, ServiceStatusHandle},
+    service_dispatcher,
+    service_manager::{ServiceManager, ServiceManagerAccess},
+};
+
+use crate::ipc::{self, Request};
+
+/// Name of the service, for `sc.exe`
+const SERVICE_NAME: &str = "hotcorners";
+
+/// Name of the service in the Services console
+const DISPLAY_NAME: &str = "Hot corners";
+
+/// Description of the service in the Services console
+const DESCRIPTION: &str = "Runs hotcorners for the user logged on at the console";
+
+/// How long the instance gets to exit through the control pipe before it is terminated
+const STOP_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// `ERROR_SERVICE_DOES_NOT_EXIST`, returned when opening a service that isn't installed
+const ERROR_SERVICE_DOES_NOT_EXIST: i32 = 1060;
+
+/// When Windows starts the service, given with `--start-type`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum StartType {
+    /// When Windows starts, the default
+    #[default]
+    Auto,
+    /// Only when started with `sc.exe start hotcorners` or from the Services console
+    Demand,
+    /// Never
+    Disabled,
+}
+
+impl FromStr for StartType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(Self::Auto),
+            "demand" => Ok(Self::Demand),
+            "disabled" => Ok(Self::Disabled),
+            _ => Err(format!(
+                "Unknown start type {s}, expected auto, demand or disabled"
+            )),
+        }
+    }
+}
+
+impl From<StartType> for ServiceStartType {
+    fn from(start_type: StartType) -> Self {
+        match start_type {
+            StartType::Auto => ServiceStartType::AutoStart,
+            StartType::Demand => ServiceStartType::OnDemand,
+            StartType::Disabled => ServiceStartType::Disabled,
+        }
+    }
+}
+
+/// Registers the current executable as a service running as `LocalSystem`, returning its path.
+/// Needs an elevated prompt.
+pub fn install(start_type: StartType) -> Result<PathBuf, String> {
+    let exe = env::current_exe().map_err(|e| format!("Failed to find hotcorners.exe: {e}"))?;
+    let manager = ServiceManager::local_computer(
+        None::<&str>,
+        ServiceManagerAccess::CONNECT | ServiceManagerAccess::CREATE_SERVICE,
+    )
+    .map_err(|e| format!("Failed to connect to the service manager, run as administrator: {e}"))?;
+
+    let info = ServiceInfo {
+        name: OsString::from(SERVICE_NAME),
+        display_name: OsString::from(DISPLAY_NAME),
+        service_type: ServiceType::OWN_PROCESS,
+        start_type: start_type.into(),
+        error_control: ServiceErrorControl::Normal,
+        executable_path: exe.clone(),
+        launch_arguments: vec![OsString::from("--run-service")],
+        dependencies: Vec::new(),
+        // `LocalSystem`, starting processes in other sessions requires it
+        account_name: None,
+        account_password: None,
+    };
+    let service = manager
+        .create_service(&info, ServiceAccess::CHANGE_CONFIG)
+        .map_err(|e| format!("Failed to install the service: {e}"))?;
+    if let Err(e) = service.set_description(DESCRIPTION) {
+        warn!("Failed to set the service description: {e}");
+    }
+    Ok(exe)
+}
+
+/// Stops and removes the service. Returns `false` if it wasn't installed.
+pub fn uninstall() -> Result<bool, String> {
+    let manager = ServiceManager::local_computer(None::<&str>, ServiceManagerAccess::CONNECT)
+        .map_err(|e| format!("Failed to connect to the service manager: {e}"))?;
+    let service = match manager.open_service(
+        SERVICE_NAME,
+        ServiceAccess::QUERY_STATUS | ServiceAccess::STOP | ServiceAccess::DELETE,
+    ) {
+        Ok(service) => service,
+        Err(windows_service::Error::Winapi(e))
+            if e.raw_os_error() == Some(ERROR_SERVICE_DOES_NOT_EXIST) =>
+        {
+            return Ok(false);
+        }
+        Err(e) => {
+            return Err(format!(
+                "Failed to open the service, run as administrator: {e}"
+            ))
+        }
+    };
+
+    let stopped = service
+        .query_status()
+        .is_ok_and(|status| status.current_state == ServiceState::Stopped);
+    if !stopped {
+        if let Err(e) = service.stop() {
+            warn!("Failed to stop the service: {e}");
+        }
+    }
+    // Removed once the service stopped and every handle to it is closed
+    service
+        .delete()
+        .map(|()| true)
+        .map_err(|e| format!("Failed to uninstall the service: {e}"))
+}
+
+define_windows_service!(ffi_service_main, service_main);
+
+/// Hands the process over to the service control manager until the service stops. Only works
+/// when the service control manager started the process, i.e. with `--run-service`.
+pub fn run() -> Result<(), String> {
+    service_dispatcher::start(SERVICE_NAME, ffi_service_main).map_err(|e| {
+        format!("Failed to start the service, --run-service is only for the service manager: {e}")
+    })
+}
+
+/// What the service control handler tells the service thread
+#[derive(Debug, Clone, Copy)]
+enum Event {
+    /// The service is stopping, or Windows is shutting down
+    Stop,
+    /// A user logged on to the session with this ID
+    Logon(u32),
+}
+
+/// Entry point of the service, called on a thread of its own by the service control dispatcher
+fn service_main(_arguments: Vec<OsString>) {
+    if let Err(e) = run_service() {
+        error!("The service failed: {e}");
+    }
+}
+
+/// Keeps an instance running for the console user until the service is stopped
+fn run_service() -> windows_service::Result<()> {
+    let (sender, receiver) = mpsc::channel();
+    let handler = move |control| match control {
+        ServiceControl::Stop | ServiceControl::Shutdown => {
+            let _ = sender.send(Event::Stop);
+            ServiceControlHandlerResult::NoError
+        }
+        ServiceControl::SessionChange(change)
+            if change.reason == SessionChangeReason::SessionLogon =>
+        {
+            let _ = sender.send(Event::Logon(change.notification.session_id));
+            ServiceControlHandlerResult::NoError
+        }
+        ServiceControl::Interrogate | ServiceControl::SessionChange(_) => {
+            ServiceControlHandlerResult::NoError
+        }
+        _ => ServiceControlHandlerResult::NotImplemented,
+    };
+    let status = service_control_handler::register(SERVICE_NAME, handler)?;
+    set_state(status, ServiceState::Running)?;
+
+    // Nobody may be logged on yet when the service starts with Windows
+    let mut instance = Instance::start(unsafe { WTSGetActiveConsoleSessionId() });
+    while let Ok(event) = receiver.recv() {
+        match event {
+            Event::Stop => break,
+            Event::Logon(session) => {
+                if !instance.as_ref().is_some_and(Instance::is_running) {
+                    instance = Instance::start(session);
+                }
+            }
+        }
+    }
+
+    set_state(status, ServiceState::StopPending)?;
+    if let Some(instance) = instance {
+        instance.stop();
+    }
+    set_state(status, ServiceState::Stopped)
+}
+
+/// Reports `state` to the service control manager
+fn set_state(status: ServiceStatusHandle, state: ServiceState) -> windows_service::Result<()> {
+    let controls_accepted = if state == ServiceState::Running {
+        ServiceControlAccept::STOP
+            | ServiceControlAccept::SHUTDOWN
+            | ServiceControlAccept::SESSION_CHANGE
+    } else {
+        ServiceControlAccept::empty()
+    };
+    status.set_service_status(ServiceStatus {
+        service_type: ServiceType::OWN_PROCESS,
+        current_state: state,
+        controls_accepted,
+        exit_code: ServiceExitCode::Win32(0),
+        checkpoint: 0,
+        wait_hint: if state == ServiceState::StopPending {
+            STOP_TIMEOUT
+        } else {
+            Duration::default()
+        },
+        process_id: None,
+    })
+}
+
+/// A hotcorners instance the service started in a user's session, the handle is closed when
+/// dropped
+#[derive(Debug)]
+struct Instance(HANDLE);
+
+impl Drop for Instance {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = CloseHandle(self.0);
+        }
+    }
+}
+
+impl Instance {
+    /// Starts hotcorners as the user logged on to `session`, if anyone is
+    fn start(session: u32) -> Option<Self> {
+        match unsafe { launch_in_session(session) } {
+            Ok(process) => {
+                debug!(session, "Started hotcorners");
+                Some(Self(process))
+            }
+            Err(e) => {
+                debug!(session, "Not starting hotcorners, nobody is logged on? {e}");
+                None
+            }
+        }
+    }
+
+    /// Whether the instance is still running, it exits when the user logs off
+    fn is_running(&self) -> bool {
+        let result = unsafe { WaitForSingleObject(self.0, 0) };
+        result == WAIT_TIMEOUT
+    }
+
+    /// Asks the instance to exit through the control pipe, terminating it if it doesn't in time
+    fn stop(self) {
+        let cmd =
+            serde_json::to_string(&Request::Shutdown).expect("requests can always be serialized");
+        if let Err(e) = ipc::connect_and_command(&cmd) {
+            warn!("Failed to stop hotcorners: {e}");
+        }
+        // `STOP_TIMEOUT` is a few seconds
+        #[allow(clippy::cast_possible_truncation)]
+        let timeout = STOP_TIMEOUT.as_millis() as u32;
+        if unsafe { WaitForSingleObject(self.0, timeout) } == WAIT_TIMEOUT {
+            warn!("hotcorners didn't exit in time, terminating it");
+            unsafe {
+                let _ = TerminateProcess(self.0, 1);
+            }
+        }
+    }
+}
+
+/// Starts the current executable without flags on the interactive desktop of `session`, as the
+/// user logged on to it. Returns the process handle.
+unsafe fn launch_in_session(session: u32) -> windows::core::Result<HANDLE> {
+    let mut token = HANDLE::default();
+    WTSQueryUserToken(session, &raw mut token)?;
+    let mut environment = std::ptr::null_mut();
+    let result = CreateEnvironmentBlock(&raw mut environment, token, false);
+    if let Err(e) = result {
+        let _ = CloseHandle(token);
+        return Err(e);
+    }
+
+    let exe = env::current_exe().unwrap_or_else(|_| PathBuf::from("hotcorners.exe"));
+    // Quoted, since the path may contain spaces
+    let mut command: Vec<u16> = OsStr::new("\"")
+        .encode_wide()
+        .chain(exe.as_os_str().encode_wide())
+        .chain(OsStr::new("\"").encode_wide())
+        .chain([0])
+        .collect();
+    let startup = STARTUPINFOW {
+        // `size_of::<STARTUPINFOW>()` will never exceed u32::MAX
+        #[allow(clippy::cast_possible_truncation)]
+        cb: std::mem::size_of::<STARTUPINFOW>() as u32,
+        // Only read, despite the mutable pointer
+        lpDesktop: PWSTR(w!(r"winsta0\default").as_ptr().cast_mut()),
+        ..Default::default()
+    };
+    let mut process = PROCESS_INFORMATION::default();
+    let result = CreateProcessAsUserW(
+        token,
+        None,
+        PWSTR(command.as_mut_ptr()),
+        None,
+        None,
+        false,
+        CREATE_UNICODE_ENVIRONMENT,
+        Some(environment.cast_const()),
+        None,
+        &raw const startup,
+        &raw mut process,
+    );
+    let _ = DestroyEnvironmentBlock(environment);
+    let _ = CloseHandle(token);
+    result?;
+
+    let _ = CloseHandle(process.hThread);
+    Ok(process.hProcess)
+}
@@ -0,0 +1,201 @@
+//! System tray icon and its context menu
+
+use std::sync::atomic::Ordering;
+
+use tracing::error;
+use windows::{
+    core::{w, Result},
+    Win32::{
+        Foundation::{HWND, LPARAM, POINT, WPARAM},
+        UI::{
+            Shell::{
+                Shell_NotifyIconW, NIF_ICON, NIF_INFO, NIF_MESSAGE, NIF_TIP, NIIF_ERROR, NIIF_INFO,
+                NIM_ADD, NIM_DELETE, NIM_MODIFY, NOTIFYICONDATAW,
+            },
+            WindowsAndMessaging::{
+                AppendMenuW, CreatePopupMenu, DestroyMenu, GetCursorPos, LoadIconW, PostMessageW,
+                PostQuitMessage, SetForegroundWindow, TrackPopupMenu, HMENU, IDI_APPLICATION,
+                MF_CHECKED, MF_SEPARATOR, MF_STRING, TPM_RETURNCMD, TPM_RIGHTBUTTON, WM_APP,
+                WM_NULL, WM_RBUTTONUP,
+            },
+        },
+    },
+};
+
+/// Message the tray icon sends to its window on mouse events
+pub const WM_TRAY: u32 = WM_APP + 1;
+
+/// ID of our only tray icon
+const TRAY_ICON_ID: u32 = 1;
+
+/// Context menu command to reload `config.toml`
+const ID_RELOAD: usize = 1;
+/// Context menu command to pause or resume every corner
+const ID_PAUSE: usize = 2;
+/// Context menu command to exit hotcorners
+const ID_EXIT: usize = 3;
+
+/// Adds the tray icon, sending its messages to `hwnd`
+pub fn add(hwnd: HWND) -> Result<()> {
+    unsafe {
+        let mut data = notify_icon_data(hwnd);
+        data.uFlags |= NIF_ICON | NIF_MESSAGE;
+        data.uCallbackMessage = WM_TRAY;
+        data.hIcon = LoadIconW(None, IDI_APPLICATION)?;
+        Shell_NotifyIconW(NIM_ADD, &raw const data).ok()
+    }
+}
+
+/// Removes the tray icon
+pub fn remove(hwnd: HWND) {
+    unsafe {
+        let data = notify_icon_data(hwnd);
+        let _ = Shell_NotifyIconW(NIM_DELETE, &raw const data);
+    }
+}
+
+/// Updates the tooltip with the number of active corners
+pub fn update_tooltip(hwnd: HWND) {
+    unsafe {
+        let data = notify_icon_data(hwnd);
+        if !Shell_NotifyIconW(NIM_MODIFY, &raw const data).as_bool() {
+            error!("Failed to update the tray icon");
+        }
+    }
+}
+
+/// Reloads `config.toml` and reports the outcome in a balloon notification
+pub fn reload_config(hwnd: HWND) {
+    match crate::reload_config() {
+        Ok(()) => notify(hwnd, "Configuration reloaded", false),
+        Err(e) => {
+            error!("{e}");
+            notify(hwnd, &e, true);
+        }
+    }
+    update_tooltip(hwnd);
+}
+
+/// Switches to the next profile and announces it in a balloon notification
+pub fn cycle_profile(hwnd: HWND) {
+    match crate::cycle_profile() {
+        Ok(name) => notify(hwnd, &format!("Switched to profile {name}"), false),
+        Err(e) => {
+            error!("{e}");
+            notify(hwnd, &e, true);
+        }
+    }
+    update_tooltip(hwnd);
+}
+
+/// Shows a balloon notification from the tray icon
+pub fn notify(hwnd: HWND, message: &str, error: bool) {
+    let mut data = notify_icon_data(hwnd);
+    data.uFlags |= NIF_INFO;
+    data.dwInfoFlags = if error { NIIF_ERROR } else { NIIF_INFO };
+    copy_wide(&mut data.szInfoTitle, "hotcorners");
+    copy_wide(&mut data.szInfo, message);
+
+    unsafe {
+        if !Shell_NotifyIconW(NIM_MODIFY, &raw const data).as_bool() {
+            error!("Failed to show a notification: {message}");
+        }
+    }
+}
+
+/// Copies `src` into the fixed-size, null-terminated string `dst`, truncating if needed
+fn copy_wide(dst: &mut [u16], src: &str) {
+    // Leave room for the terminating null
+    let max_len = dst.len() - 1;
+    for (dst, src) in dst.iter_mut().zip(src.encode_utf16().take(max_len)) {
+        *dst = src;
+    }
+}
+
+/// Describes our tray icon, including the current tooltip
+fn notify_icon_data(hwnd: HWND) -> NOTIFYICONDATAW {
+    let active = crate::active_corners();
+    let profile = crate::active_profile()
+        .map(|name| format!(" — {name}"))
+        .unwrap_or_default();
+    let tooltip = format!(
+        "hotcorners{profile} — {active} corner{} active",
+        if active == 1 { "" } else { "s" }
+    );
+
+    let mut data = NOTIFYICONDATAW {
+        // `size_of::<NOTIFYICONDATAW>()` will never exceed u32::MAX
+        #[allow(clippy::cast_possible_truncation)]
+        cbSize: std::mem::size_of::<NOTIFYICONDATAW>() as u32,
+        hWnd: hwnd,
+        uID: TRAY_ICON_ID,
+        uFlags: NIF_TIP,
+        ..Default::default()
+    };
+    copy_wide(&mut data.szTip, &tooltip);
+
+    data
+}
+
+/// Handles a `WM_TRAY` message sent to `hwnd`
+pub fn handle_message(hwnd: HWND, l_param: LPARAM) {
+    // The low word of `l_param` holds the mouse message
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    if (l_param.0 as u32 & 0xFFFF) == WM_RBUTTONUP {
+        if let Err(e) = show_menu(hwnd) {
+            error!("Failed to show the tray menu: {e}");
+        }
+    }
+}
+
+/// Shows the context menu at the cursor and runs the selected command
+fn show_menu(hwnd: HWND) -> Result<()> {
+    unsafe {
+        let menu = CreatePopupMenu()?;
+        let result = track_menu(hwnd, menu);
+        DestroyMenu(menu)?;
+
+        match result? {
+            ID_RELOAD => reload_config(hwnd),
+            ID_PAUSE => {
+                crate::PAUSED.fetch_xor(true, Ordering::Relaxed);
+            }
+            ID_EXIT => PostQuitMessage(0),
+            _ => return Ok(()),
+        }
+        update_tooltip(hwnd);
+
+        Ok(())
+    }
+}
+
+/// Fills `menu`, shows it and returns the selected command, or 0 if the menu was dismissed
+unsafe fn track_menu(hwnd: HWND, menu: HMENU) -> Result<usize> {
+    let paused = if crate::PAUSED.load(Ordering::Relaxed) {
+        MF_CHECKED
+    } else {
+        MF_STRING
+    };
+    AppendMenuW(menu, MF_STRING, ID_RELOAD, w!("Reload Config"))?;
+    AppendMenuW(menu, MF_STRING | paused, ID_PAUSE, w!("Pause All"))?;
+    AppendMenuW(menu, MF_SEPARATOR, 0, None)?;
+    AppendMenuW(menu, MF_STRING, ID_EXIT, w!("Exit"))?;
+
+    let mut pt = POINT::default();
+    GetCursorPos(&raw mut pt)?;
+
+    // The menu only closes when clicking elsewhere if our window is in the foreground
+    let _ = SetForegroundWindow(hwnd);
+    let selected = TrackPopupMenu(
+        menu,
+        TPM_RETURNCMD | TPM_RIGHTBUTTON,
+        pt.x,
+        pt.y,
+        0,
+        hwnd,
+        None,
+    );
+    PostMessageW(hwnd, WM_NULL, WPARAM(0), LPARAM(0))?;
+
+    Ok(usize::try_from(selected.0).unwrap_or(0))
+}
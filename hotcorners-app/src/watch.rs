@@ -0,0 +1,141 @@
+//! Watches `config.toml` for changes so it can be reloaded automatically
+
+use std::{
+    ffi::{OsStr, OsString},
+    os::windows::ffi::{OsStrExt, OsStringExt},
+    path::Path,
+    thread,
+    time::Duration,
+};
+
+use tracing::error;
+use windows::{
+    core::PCWSTR,
+    Win32::{
+        Foundation::{CloseHandle, HANDLE, HWND, LPARAM, WPARAM},
+        Storage::FileSystem::{
+            CreateFileW, ReadDirectoryChangesW, FILE_FLAG_BACKUP_SEMANTICS, FILE_LIST_DIRECTORY,
+            FILE_NOTIFY_CHANGE_FILE_NAME, FILE_NOTIFY_CHANGE_LAST_WRITE, FILE_NOTIFY_INFORMATION,
+            FILE_SHARE_DELETE, FILE_SHARE_READ, FILE_SHARE_WRITE, OPEN_EXISTING,
+        },
+        UI::WindowsAndMessaging::PostMessageW,
+    },
+};
+
+use crate::window::{SendHwnd, WM_CONFIG_CHANGED};
+
+/// Editors often write a file in several steps, wait for them to finish before reloading
+const SETTLE_DELAY: Duration = Duration::from_millis(200);
+
+/// Starts a background thread that posts `WM_CONFIG_CHANGED` to `hwnd` whenever the file at
+/// `path` is written, created or renamed.
+///
+/// Does nothing if the directory containing `path` doesn't exist.
+pub fn watch(path: &Path, hwnd: HWND) {
+    let Some(dir) = path
+        .parent()
+        .filter(|dir| dir.is_dir())
+        .map(Path::to_path_buf)
+    else {
+        return;
+    };
+    let Some(file_name) = path.file_name().map(OsStr::to_ascii_lowercase) else {
+        return;
+    };
+
+    let hwnd = SendHwnd::new(hwnd);
+    thread::spawn(move || {
+        let handle = match open_directory(&dir) {
+            Ok(handle) => handle,
+            Err(e) => {
+                error!("Failed to watch {}: {e}", dir.display());
+                return;
+            }
+        };
+
+        while wait_for_change(handle, &file_name) {
+            thread::sleep(SETTLE_DELAY);
+            unsafe {
+                if PostMessageW(hwnd.get(), WM_CONFIG_CHANGED, WPARAM(0), LPARAM(0)).is_err() {
+                    break;
+                }
+            }
+        }
+
+        unsafe {
+            let _ = CloseHandle(handle);
+        }
+    });
+}
+
+/// Opens `dir` for `ReadDirectoryChangesW`
+fn open_directory(dir: &Path) -> windows::core::Result<HANDLE> {
+    let wide: Vec<u16> = dir.as_os_str().encode_wide().chain(Some(0)).collect();
+    unsafe {
+        CreateFileW(
+            PCWSTR(wide.as_ptr()),
+            FILE_LIST_DIRECTORY.0,
+            FILE_SHARE_READ | FILE_SHARE_WRITE | FILE_SHARE_DELETE,
+            None,
+            OPEN_EXISTING,
+            FILE_FLAG_BACKUP_SEMANTICS,
+            None,
+        )
+    }
+}
+
+/// Blocks until a file named `file_name` (lowercase) changes in the directory behind `handle`.
+/// Returns `false` if the directory can no longer be watched.
+fn wait_for_change(handle: HANDLE, file_name: &OsString) -> bool {
+    // `FILE_NOTIFY_INFORMATION` entries are `u32` aligned
+    let mut buffer = [0u32; 1024];
+
+    loop {
+        let mut len = 0u32;
+        let result = unsafe {
+            ReadDirectoryChangesW(
+                handle,
+                buffer.as_mut_ptr().cast(),
+                // `buffer` is 4 KiB
+                #[allow(clippy::cast_possible_truncation)]
+                {
+                    std::mem::size_of_val(&buffer) as u32
+                },
+                false,
+                FILE_NOTIFY_CHANGE_LAST_WRITE | FILE_NOTIFY_CHANGE_FILE_NAME,
+                Some(&raw mut len),
+                None,
+                None,
+            )
+        };
+        if result.is_err() {
+            return false;
+        }
+        // The buffer overflowed, so we don't know what changed
+        if len == 0 {
+            return true;
+        }
+
+        let mut offset = 0usize;
+        loop {
+            unsafe {
+                let info = buffer
+                    .as_ptr()
+                    .add(offset / 4)
+                    .cast::<FILE_NOTIFY_INFORMATION>();
+                let name = std::slice::from_raw_parts(
+                    (*info).FileName.as_ptr(),
+                    (*info).FileNameLength as usize / 2,
+                );
+                if OsString::from_wide(name).to_ascii_lowercase() == *file_name {
+                    return true;
+                }
+
+                if (*info).NextEntryOffset == 0 {
+                    break;
+                }
+                offset += (*info).NextEntryOffset as usize;
+            }
+        }
+    }
+}
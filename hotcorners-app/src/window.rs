@@ -0,0 +1,203 @@
+//! Hidden window that receives tray icon and system broadcast messages
+
+use std::sync::atomic::Ordering;
+
+use tracing::error;
+use windows::{
+    core::{w, Result},
+    Win32::{
+        Foundation::{HINSTANCE, HWND, LPARAM, LRESULT, WPARAM},
+        System::{
+            LibraryLoader::GetModuleHandleW,
+            RemoteDesktop::{
+                WTSRegisterSessionNotification, WTSUnRegisterSessionNotification,
+                NOTIFY_FOR_THIS_SESSION,
+            },
+        },
+        UI::WindowsAndMessaging::{
+            CreateWindowExW, DefWindowProcW, KillTimer, PostQuitMessage, RegisterClassW, SetTimer,
+            PBT_APMPOWERSTATUSCHANGE, PBT_APMRESUMEAUTOMATIC, WINDOW_EX_STYLE, WINDOW_STYLE,
+            WM_APP, WM_DISPLAYCHANGE, WM_ENDSESSION, WM_POWERBROADCAST, WM_QUERYENDSESSION,
+            WM_TIMER, WM_WTSSESSION_CHANGE, WNDCLASSW, WTS_CONSOLE_CONNECT, WTS_REMOTE_CONNECT,
+            WTS_REMOTE_DISCONNECT, WTS_SESSION_LOCK, WTS_SESSION_UNLOCK,
+        },
+    },
+};
+
+use hotcorners_core::power;
+
+use crate::tray;
+
+/// Posted by the config watcher when `config.toml` changed
+pub const WM_CONFIG_CHANGED: u32 = WM_APP + 2;
+
+/// Posted by the control pipe to make hotcorners exit
+pub const WM_SHUTDOWN: u32 = WM_APP + 3;
+
+/// ID of the timer that reinstalls the hooks after a resume
+const RESUME_TIMER_ID: usize = 1;
+
+/// How long to wait after a resume before reinstalling the hooks, to let drivers settle
+const RESUME_DELAY_MS: u32 = 100;
+
+/// ID of the timer that ends `--simulate`
+const SIMULATE_TIMER_ID: usize = 2;
+
+/// How long `--simulate` runs unless the exit hotkey is pressed first
+const SIMULATE_DURATION_MS: u32 = 30_000;
+
+/// A window handle that can be moved to another thread.
+///
+/// `HWND` isn't `Send` since it wraps a pointer, but window handles aren't tied to the thread
+/// that created the window, so other threads can post messages to it.
+#[derive(Debug, Clone, Copy)]
+pub struct SendHwnd(isize);
+
+impl SendHwnd {
+    /// Wraps `hwnd`
+    pub fn new(hwnd: HWND) -> Self {
+        Self(hwnd.0 as isize)
+    }
+
+    /// The wrapped handle
+    pub fn get(self) -> HWND {
+        HWND(self.0 as *mut _)
+    }
+}
+
+/// Creates the hidden window.
+///
+/// The window belongs to the calling thread, so that thread must run the message loop. It is a
+/// regular top-level window rather than a message-only window, since those don't receive
+/// broadcasts such as `WM_DISPLAYCHANGE`.
+pub fn create() -> Result<HWND> {
+    unsafe {
+        let instance: HINSTANCE = GetModuleHandleW(None)?.into();
+        let class_name = w!("hotcorners");
+
+        let class = WNDCLASSW {
+            lpfnWndProc: Some(window_proc),
+            hInstance: instance,
+            lpszClassName: class_name,
+            ..Default::default()
+        };
+        if RegisterClassW(&raw const class) == 0 {
+            return Err(windows::core::Error::from_win32());
+        }
+
+        // Never shown, it only exists to receive messages
+        CreateWindowExW(
+            WINDOW_EX_STYLE::default(),
+            class_name,
+            w!("hotcorners"),
+            WINDOW_STYLE::default(),
+            0,
+            0,
+            0,
+            0,
+            None,
+            None,
+            instance,
+            None,
+        )
+    }
+}
+
+/// Makes the message loop of `hwnd` quit once `--simulate` ran for `SIMULATE_DURATION_MS`
+pub fn start_simulation_timer(hwnd: HWND) {
+    unsafe {
+        SetTimer(hwnd, SIMULATE_TIMER_ID, SIMULATE_DURATION_MS, None);
+    }
+}
+
+/// Starts sending `WM_WTSSESSION_CHANGE` to `hwnd` when the session is locked or unlocked
+pub fn register_session_notifications(hwnd: HWND) {
+    if let Err(e) = unsafe { WTSRegisterSessionNotification(hwnd, NOTIFY_FOR_THIS_SESSION) } {
+        error!("Failed to register for session notifications: {e}");
+    }
+}
+
+/// Undoes `register_session_notifications`
+pub fn unregister_session_notifications(hwnd: HWND) {
+    unsafe {
+        let _ = WTSUnRegisterSessionNotification(hwnd);
+    }
+}
+
+/// Window procedure for the hidden window
+extern "system" fn window_proc(hwnd: HWND, msg: u32, w_param: WPARAM, l_param: LPARAM) -> LRESULT {
+    match msg {
+        #[cfg(feature = "tray-icon")]
+        tray::WM_TRAY => {
+            tray::handle_message(hwnd, l_param);
+            LRESULT(0)
+        }
+        WM_CONFIG_CHANGED => {
+            tray::reload_config(hwnd);
+            LRESULT(0)
+        }
+        WM_SHUTDOWN => {
+            unsafe { PostQuitMessage(0) };
+            LRESULT(0)
+        }
+        // Windows is shutting down or the user logs off. Agree and exit, so hotcorners doesn't
+        // show up as blocking the shutdown.
+        WM_QUERYENDSESSION => {
+            unsafe { PostQuitMessage(0) };
+            LRESULT(1)
+        }
+        // The process can be terminated any time after this returns, so don't wait for the
+        // message loop to get to the quit message
+        WM_ENDSESSION => {
+            if w_param.0 != 0 {
+                crate::cleanup(hwnd);
+            }
+            LRESULT(0)
+        }
+        WM_WTSSESSION_CHANGE => {
+            // Nobody is at the screen while the session is locked
+            #[allow(clippy::cast_possible_truncation)]
+            match w_param.0 as u32 {
+                WTS_SESSION_LOCK => crate::SESSION_LOCKED.store(true, Ordering::Relaxed),
+                WTS_SESSION_UNLOCK => crate::SESSION_LOCKED.store(false, Ordering::Relaxed),
+                // The session moves between the local console and a Remote Desktop client
+                WTS_CONSOLE_CONNECT | WTS_REMOTE_CONNECT | WTS_REMOTE_DISCONNECT => {
+                    crate::update_remote_session();
+                }
+                _ => {}
+            }
+            LRESULT(0)
+        }
+        WM_POWERBROADCAST => {
+            #[allow(clippy::cast_possible_truncation)]
+            match w_param.0 as u32 {
+                PBT_APMRESUMEAUTOMATIC => unsafe {
+                    SetTimer(hwnd, RESUME_TIMER_ID, RESUME_DELAY_MS, None);
+                },
+                PBT_APMPOWERSTATUSCHANGE => power::update(),
+                _ => {}
+            }
+            LRESULT(1)
+        }
+        WM_TIMER if w_param.0 == RESUME_TIMER_ID => {
+            unsafe {
+                let _ = KillTimer(hwnd, RESUME_TIMER_ID);
+            }
+            crate::reinstall_hooks();
+            LRESULT(0)
+        }
+        WM_TIMER if w_param.0 == SIMULATE_TIMER_ID => {
+            unsafe {
+                let _ = KillTimer(hwnd, SIMULATE_TIMER_ID);
+                PostQuitMessage(0);
+            }
+            LRESULT(0)
+        }
+        WM_DISPLAYCHANGE => {
+            crate::rebuild_corners();
+            tray::update_tooltip(hwnd);
+            LRESULT(0)
+        }
+        _ => unsafe { DefWindowProcW(hwnd, msg, w_param, l_param) },
+    }
+}
@@ -0,0 +1,122 @@
+//! Runs the hotcorners executable with a test configuration and moves the real cursor into a
+//! corner.
+//!
+//! This needs an interactive desktop that accepts `SendInput`, like the Windows CI runners. The
+//! test moves the cursor and, while it runs, another hotcorners instance with a top-left corner
+//! would activate too, so it's ignored unless asked for with `cargo test -- --include-ignored`.
+
+use std::{
+    fs,
+    path::PathBuf,
+    process::{Child, Command},
+    thread,
+    time::{Duration, Instant},
+};
+
+use windows::Win32::UI::Input::KeyboardAndMouse::{
+    SendInput, INPUT, INPUT_0, INPUT_MOUSE, MOUSEEVENTF_ABSOLUTE, MOUSEEVENTF_MOVE, MOUSEINPUT,
+};
+
+/// How long the test waits for the corner to activate, including the startup of hotcorners
+const ACTIVATION_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long the cursor stays at each end of a move into the corner
+const MOVE_INTERVAL: Duration = Duration::from_millis(200);
+
+/// A hotcorners process and its files, killed and deleted when dropped, even if the test fails.
+/// Killing the process also removes its mouse hook.
+struct Hotcorners {
+    child: Child,
+    dir: PathBuf,
+}
+
+impl Hotcorners {
+    /// Starts hotcorners under its own instance name, writing `config` to a new temporary
+    /// directory first. `{log_file}` in `config` is replaced with the path of the activation log.
+    fn start(config: &str) -> Self {
+        let dir = std::env::temp_dir().join(format!("hotcorners-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).expect("the test directory can be created");
+        let config_path = dir.join("config.toml");
+        let log_file = dir.join("activations.jsonl");
+        let config = config.replace("{log_file}", &log_file.display().to_string());
+        fs::write(&config_path, config).expect("the test config can be written");
+
+        let child = Command::new(env!("CARGO_BIN_EXE_hotcorners"))
+            .arg("--config")
+            .arg(&config_path)
+            .arg("--instance-name")
+            .arg(format!("integration-test-{}", std::process::id()))
+            .spawn()
+            .expect("hotcorners can be started");
+        Self { child, dir }
+    }
+
+    /// The activation log, empty until the first activation
+    fn activations(&self) -> String {
+        fs::read_to_string(self.dir.join("activations.jsonl")).unwrap_or_default()
+    }
+}
+
+impl Drop for Hotcorners {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// Moves the cursor to `(x, y)` on the primary monitor, as fractions of its width and height
+fn move_cursor(x: f64, y: f64) {
+    // `SendInput` maps absolute coordinates from 0 to 65535 onto the primary monitor
+    #[allow(clippy::cast_possible_truncation)]
+    let input = INPUT {
+        r#type: INPUT_MOUSE,
+        Anonymous: INPUT_0 {
+            mi: MOUSEINPUT {
+                dx: (x * 65535.0) as i32,
+                dy: (y * 65535.0) as i32,
+                dwFlags: MOUSEEVENTF_MOVE | MOUSEEVENTF_ABSOLUTE,
+                ..Default::default()
+            },
+        },
+    };
+    // `size_of::<INPUT>()` will never > i32::MAX
+    #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+    let sent = unsafe { SendInput(&[input], std::mem::size_of::<INPUT>() as i32) };
+    assert_eq!(sent, 1, "the desktop accepts injected input");
+}
+
+#[test]
+#[ignore = "moves the cursor, run with --include-ignored"]
+fn entering_the_top_left_corner_activates_it() {
+    let hotcorners = Hotcorners::start(
+        r#"
+suppress_fullscreen = false
+log_file = '{log_file}'
+
+[[corners]]
+position = "top-left"
+delay_ms = 0
+cooldown_ms = 0
+action = { type = "exec", path = 'C:\Windows\System32\cmd.exe', args = ["/C", "exit"] }
+"#,
+    );
+
+    // The hook is only installed a while after the process started, so keep entering the corner
+    // from the middle of the screen until it activates
+    let start = Instant::now();
+    while start.elapsed() < ACTIVATION_TIMEOUT {
+        move_cursor(0.5, 0.5);
+        thread::sleep(MOVE_INTERVAL);
+        move_cursor(0.0, 0.0);
+        thread::sleep(MOVE_INTERVAL);
+
+        if hotcorners.activations().contains(r#""corner":"top-left""#) {
+            return;
+        }
+    }
+    panic!(
+        "the top-left corner didn't activate within {ACTIVATION_TIMEOUT:?}, activation log: {:?}",
+        hotcorners.activations()
+    );
+}
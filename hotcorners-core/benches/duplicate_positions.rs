@@ -0,0 +1,109 @@
+//! Mouse hook work with and without skipping events at the position of the previous one, for a
+//! second of a 1000Hz touchpad that mostly reports the cursor standing still
+// `criterion_group!` declares an undocumented public function
+#![allow(missing_docs)]
+
+use std::{
+    hint::black_box,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use hotcorners_core::{
+    corners::{compute_corner_rect, pack_point, Corner, CornerState, HotZone, DEFAULT_DPI},
+    monitors::MonitorInfo,
+    Config,
+};
+use windows::Win32::Foundation::{POINT, RECT};
+
+/// Events per second at a 1000Hz polling rate
+const EVENTS: i32 = 1000;
+/// Every this many events the cursor actually moves, the others repeat its position
+const MOVE_EVERY: i32 = 5;
+
+/// A 1920x1080 primary monitor at 100% scaling
+fn monitor() -> MonitorInfo {
+    let bounds = RECT {
+        left: 0,
+        top: 0,
+        right: 1920,
+        bottom: 1080,
+    };
+    MonitorInfo {
+        index: 0,
+        device_name: r"\\.\DISPLAY1".to_owned(),
+        bounds,
+        work_area: bounds,
+        dpi: DEFAULT_DPI,
+        is_primary: true,
+    }
+}
+
+/// The cursor drifting towards the top-left corner, only moving every `MOVE_EVERY` events
+fn events() -> Vec<POINT> {
+    (0..EVENTS)
+        .map(|i| {
+            let step = i / MOVE_EVERY;
+            POINT {
+                x: 400 - step,
+                y: 300 - step,
+            }
+        })
+        .collect()
+}
+
+/// What the hook does for an event that reaches the corners
+fn process(states: &[CornerState], pt: POINT) {
+    for state in states {
+        if state.contains(pt) {
+            black_box(state.record_inside());
+        } else {
+            state.record_outside(pt, 0);
+        }
+    }
+}
+
+fn duplicate_positions(c: &mut Criterion) {
+    let config = Config::default();
+    let monitor = monitor();
+    let states: Vec<CornerState> = config
+        .corners
+        .iter()
+        .map(|corner| {
+            let size = config.corner_size(corner);
+            CornerState::new(&HotZone {
+                config: corner,
+                rect: compute_corner_rect(Corner::TopLeft, size, &monitor),
+                monitor: monitor.bounds,
+                size,
+                send_input_retry: None,
+            })
+        })
+        .collect();
+    let events = events();
+
+    let mut group = c.benchmark_group("mouse hook");
+    group.bench_function("every event", |b| {
+        b.iter(|| {
+            for &pt in &events {
+                process(&states, black_box(pt));
+            }
+        });
+    });
+    group.bench_function("skipping duplicate positions", |b| {
+        b.iter(|| {
+            let last = AtomicU64::new(u64::MAX);
+            for &pt in &events {
+                let point = pack_point(black_box(pt));
+                if last.swap(point, Ordering::Relaxed) == point {
+                    continue;
+                }
+                process(&states, pt);
+            }
+        });
+    });
+    group.finish();
+}
+
+criterion_group!(benches, duplicate_positions);
+criterion_main!(benches);
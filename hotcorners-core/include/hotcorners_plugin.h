@@ -0,0 +1,68 @@
+/*
+ * hotcorners plugin ABI
+ *
+ * A plugin is a DLL that exports both functions declared below. It's loaded at startup from one
+ * of the `plugin_paths` in config.toml, and provides the action type named by its descriptor:
+ *
+ *     action = { type = "plugin", name = "my-action" }
+ *
+ * Plugins run inside the hotcorners process, on their own thread. Describing a plugin may take
+ * at most 5 seconds and an action at most 30, after which hotcorners stops waiting for it.
+ */
+#ifndef HOTCORNERS_PLUGIN_H
+#define HOTCORNERS_PLUGIN_H
+
+#include <stdint.h>
+#include <windows.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Version of this header, checked against `abi_version` of the descriptor */
+#define HOTCORNERS_PLUGIN_ABI_VERSION 1
+
+/* The corner that activated, in `HotCornersActionContext.corner` */
+typedef enum HotCornersCorner {
+    HOTCORNERS_TOP_LEFT = 0,
+    HOTCORNERS_TOP_RIGHT = 1,
+    HOTCORNERS_BOTTOM_LEFT = 2,
+    HOTCORNERS_BOTTOM_RIGHT = 3,
+    HOTCORNERS_LEFT_EDGE = 4,
+    HOTCORNERS_RIGHT_EDGE = 5,
+    HOTCORNERS_TOP_EDGE = 6,
+    HOTCORNERS_BOTTOM_EDGE = 7,
+} HotCornersCorner;
+
+/* Returned by `hotcorners_plugin_describe` */
+typedef struct HotCornersPluginDescriptor {
+    /* HOTCORNERS_PLUGIN_ABI_VERSION */
+    uint32_t abi_version;
+    /* Name of the action type, NUL-terminated UTF-8 that stays valid while the DLL is loaded */
+    const char *name;
+} HotCornersPluginDescriptor;
+
+/* Passed to `hotcorners_action_execute`, only valid during the call */
+typedef struct HotCornersActionContext {
+    /* HOTCORNERS_PLUGIN_ABI_VERSION */
+    uint32_t abi_version;
+    /* A HotCornersCorner */
+    uint32_t corner;
+    /* Cursor position in physical screen coordinates when the action started */
+    int32_t cursor_x;
+    int32_t cursor_y;
+    /* Sends `count` inputs like SendInput, returning how many were sent */
+    uint32_t (*send_input)(const INPUT *inputs, uint32_t count);
+} HotCornersActionContext;
+
+/* Describes the plugin, called once when it's loaded */
+__declspec(dllexport) HotCornersPluginDescriptor hotcorners_plugin_describe(void);
+
+/* Runs the action, returning 0 on success and anything else on failure */
+__declspec(dllexport) int32_t hotcorners_action_execute(const HotCornersActionContext *ctx);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* HOTCORNERS_PLUGIN_H */
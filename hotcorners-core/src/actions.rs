@@ -0,0 +1,513 @@
+//! Implementations of the corner actions that don't just send input
+
+use std::{
+    env,
+    fmt::{self, Write},
+    io,
+    os::windows::process::CommandExt,
+    path::{Path, PathBuf},
+    process::Command,
+    time::Duration,
+};
+
+use windows::{
+    core::{w, HRESULT, HSTRING},
+    Wdk::System::SystemServices::RtlGetVersion,
+    Win32::{
+        Foundation::{CloseHandle, WIN32_ERROR},
+        System::{
+            Diagnostics::ToolHelp::{
+                CreateToolhelp32Snapshot, Process32FirstW, Process32NextW, PROCESSENTRY32W,
+                TH32CS_SNAPPROCESS,
+            },
+            Registry::{RegGetValueW, HKEY_CURRENT_USER, RRF_RT_REG_DWORD},
+            Shutdown::LockWorkStation,
+            SystemInformation::OSVERSIONINFOW,
+            Threading::{CREATE_NEW_PROCESS_GROUP, DETACHED_PROCESS},
+        },
+        UI::{Shell::ShellExecuteW, WindowsAndMessaging::SW_SHOWNORMAL},
+    },
+};
+
+/// Key with the Game Bar's capture settings, under `HKEY_CURRENT_USER`
+const GAME_DVR_KEY: &str = r"SOFTWARE\Microsoft\Windows\CurrentVersion\GameDVR";
+
+/// First build of Windows 11
+pub const WINDOWS_11_BUILD: u32 = 22000;
+
+/// First Windows build with the Copilot sidebar, Windows 11 22H2
+pub const COPILOT_MIN_BUILD: u32 = 22621;
+
+/// Pages of the Settings app the `settings` action knows about, the part after `ms-settings:`
+pub const SETTINGS_PAGES: &[&str] = &[
+    "about",
+    "appsfeatures",
+    "batterysaver",
+    "bluetooth",
+    "clipboard",
+    "colors",
+    "connecteddevices",
+    "dateandtime",
+    "defaultapps",
+    "display",
+    "emailandaccounts",
+    "keyboard",
+    "lockscreen",
+    "mousetouchpad",
+    "multitasking",
+    "network",
+    "network-airplanemode",
+    "network-ethernet",
+    "network-vpn",
+    "network-wifi",
+    "nightlight",
+    "notifications",
+    "personalization",
+    "personalization-background",
+    "powersleep",
+    "printers",
+    "privacy",
+    "quiethours",
+    "regionlanguage",
+    "sound",
+    "startupapps",
+    "storagesense",
+    "taskbar",
+    "themes",
+    "windowsupdate",
+    "yourinfo",
+];
+
+/// URL schemes the `url` action can always open
+pub const DEFAULT_URL_SCHEMES: [&str; 2] = ["http", "https"];
+
+/// Why a corner's action failed
+#[derive(Debug)]
+pub enum ActionError {
+    /// `SendInput` sent fewer events than it was given, e.g. because another program blocks input
+    SendInputFailed {
+        /// Events that were sent
+        sent: u32,
+        /// Events that should have been sent
+        expected: u32,
+    },
+    /// A program couldn't be started
+    ProcessLaunchFailed {
+        /// The program
+        path: PathBuf,
+        /// Why it couldn't be started
+        error: io::Error,
+    },
+    /// A COM interface or another Windows API that reports an `HRESULT` failed
+    ComFailed {
+        /// The `HRESULT`
+        hresult: i32,
+        /// What the action was doing, e.g. `toggle mute`
+        context: String,
+    },
+    /// A Win32 function that reports failures through `GetLastError` or a `WIN32_ERROR` failed
+    Win32Failed {
+        /// The Win32 error code, e.g. 5 for `ERROR_ACCESS_DENIED`, or the bits of the negative
+        /// `DISP_CHANGE` value a display settings function returned
+        code: u32,
+        /// What the action was doing, e.g. `lock the screen`
+        context: String,
+    },
+    /// `ShellExecuteW` couldn't open a URI or program
+    ShellExecuteFailed {
+        /// What was opened
+        uri: String,
+        /// What `ShellExecuteW` returned, a value up to 32
+        error: i32,
+    },
+    /// Something the action works on doesn't exist, e.g. the window it brings back
+    NotFound {
+        /// What wasn't found, e.g. `the on-screen keyboard window`
+        what: String,
+    },
+    /// State the action changes is stored in a format hotcorners doesn't know, e.g. by a newer
+    /// version of Windows
+    UnknownFormat {
+        /// What has the unknown format, e.g. `the Night Light state`
+        what: String,
+    },
+    /// A Rhai script didn't compile or failed while running
+    ScriptFailed {
+        /// File the script was read from, `None` for an inline script
+        path: Option<PathBuf>,
+        /// Rhai's description of the error
+        message: String,
+    },
+    /// A file system operation or starting a thread failed
+    Io {
+        /// What the action was doing, e.g. `create C:\Screenshots`
+        context: String,
+        /// Why it failed
+        error: io::Error,
+    },
+    /// A plugin's action returned a code other than 0
+    PluginFailed {
+        /// Name of the plugin's action type
+        name: String,
+        /// The code it returned
+        code: i32,
+    },
+    /// Something the action waits for didn't finish in time
+    TimedOut {
+        /// What didn't finish, e.g. `The x plugin`
+        what: String,
+        /// How long the action waited
+        timeout: Duration,
+    },
+}
+
+impl ActionError {
+    /// A `ComFailed` error for `error`, which happened while trying to do `context`
+    pub fn com(error: &windows::core::Error, context: &str) -> Self {
+        Self::ComFailed {
+            hresult: error.code().0,
+            context: context.to_owned(),
+        }
+    }
+
+    /// A `Win32Failed` error for `error`, which happened while trying to do `context`. Errors that
+    /// don't wrap a Win32 error code keep the bits of their `HRESULT` as the code.
+    pub fn win32(error: &windows::core::Error, context: &str) -> Self {
+        // Reinterpreting the bits is intended, `HRESULT::from_win32` passes failure codes through
+        #[allow(clippy::cast_sign_loss)]
+        let code = WIN32_ERROR::from_error(error).map_or(error.code().0 as u32, |code| code.0);
+        Self::Win32Failed {
+            code,
+            context: context.to_owned(),
+        }
+    }
+}
+
+impl fmt::Display for ActionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::SendInputFailed { sent, expected } => {
+                write!(f, "Sent {sent} of {expected} input events")
+            }
+            Self::ProcessLaunchFailed { path, error } => {
+                write!(f, "Failed to launch {}: {error}", path.display())
+            }
+            Self::ComFailed { hresult, context } => write!(
+                f,
+                "Failed to {context}: {} ({hresult:#010X})",
+                HRESULT(*hresult).message()
+            ),
+            Self::Win32Failed { code, context } => {
+                // `DISP_CHANGE` values have no system message
+                let message = HRESULT::from_win32(*code).message();
+                if message.is_empty() {
+                    write!(f, "Failed to {context} (error {code})")
+                } else {
+                    write!(f, "Failed to {context}: {message} (error {code})")
+                }
+            }
+            Self::ShellExecuteFailed { uri, error } => {
+                write!(f, "Failed to open {uri}, ShellExecuteW returned {error}")
+            }
+            Self::NotFound { what } => write!(f, "Found no {what}"),
+            Self::UnknownFormat { what } => write!(f, "Unknown format of {what}"),
+            Self::ScriptFailed {
+                path: Some(path),
+                message,
+            } => write!(f, "The script {} failed: {message}", path.display()),
+            Self::ScriptFailed {
+                path: None,
+                message,
+            } => write!(f, "The script failed: {message}"),
+            Self::Io { context, error } => write!(f, "Failed to {context}: {error}"),
+            Self::PluginFailed { name, code } => write!(f, "The {name} plugin returned {code}"),
+            Self::TimedOut { what, timeout } => {
+                write!(f, "{what} didn't finish within {}s", timeout.as_secs())
+            }
+        }
+    }
+}
+
+impl std::error::Error for ActionError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::ProcessLaunchFailed { error, .. } | Self::Io { error, .. } => Some(error),
+            _ => None,
+        }
+    }
+}
+
+/// Launches `path` with `args` as a detached process, so it neither shares our console nor
+/// receives our Ctrl+C, and we never wait on it.
+pub fn spawn_detached(path: &Path, args: &[String]) -> io::Result<()> {
+    Command::new(path)
+        .args(args)
+        .creation_flags(DETACHED_PROCESS.0 | CREATE_NEW_PROCESS_GROUP.0)
+        .spawn()
+        .map(drop)
+}
+
+/// Whether `spawn_detached` would find `path`, either as given or, for a bare file name, in one
+/// of the directories on `PATH`
+pub fn program_exists(path: &Path) -> bool {
+    if path.is_file() {
+        return true;
+    }
+    if path.components().count() > 1 {
+        return false;
+    }
+
+    let with_exe = path.with_extension("exe");
+    env::var_os("PATH").is_some_and(|dirs| {
+        env::split_paths(&dirs).any(|dir| dir.join(path).is_file() || dir.join(&with_exe).is_file())
+    })
+}
+
+/// Locks the workstation, the same as pressing `Win+L`
+pub fn lock_screen() -> windows::core::Result<()> {
+    unsafe { LockWorkStation() }
+}
+
+/// Opens `uri` with the program registered for it, e.g. a web page in the default browser
+pub fn shell_execute_uri(uri: &str) -> Result<(), ActionError> {
+    let result = unsafe {
+        ShellExecuteW(
+            None,
+            w!("open"),
+            &HSTRING::from(uri),
+            None,
+            None,
+            SW_SHOWNORMAL,
+        )
+    };
+    // Values up to 32 are error codes
+    let code = result.0 as usize;
+    if code <= 32 {
+        return Err(ActionError::ShellExecuteFailed {
+            uri: uri.to_owned(),
+            error: i32::try_from(code).unwrap_or_default(),
+        });
+    }
+    Ok(())
+}
+
+/// Scheme of `url` in lower case, e.g. `https`, if it has one
+pub fn url_scheme(url: &str) -> Option<String> {
+    let (scheme, _) = url.split_once(':')?;
+    let valid = scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+        && scheme
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'));
+    valid.then(|| scheme.to_ascii_lowercase())
+}
+
+/// Percent-encodes the characters of `url` that aren't allowed in a URL as they are, non-ASCII
+/// characters, spaces and control characters
+pub fn percent_encode_url(url: &str) -> String {
+    let mut encoded = String::with_capacity(url.len());
+    for c in url.chars() {
+        if c.is_ascii_graphic() {
+            encoded.push(c);
+        } else {
+            let mut bytes = [0; 4];
+            for byte in c.encode_utf8(&mut bytes).bytes() {
+                let _ = write!(encoded, "%{byte:02X}");
+            }
+        }
+    }
+    encoded
+}
+
+/// Build number of the running Windows, e.g. `22631`.
+///
+/// Uses `RtlGetVersion`, since `GetVersionExW` reports Windows 8 to programs without a manifest.
+pub fn windows_build() -> Option<u32> {
+    let mut info = OSVERSIONINFOW {
+        // `size_of::<OSVERSIONINFOW>()` will never exceed u32::MAX
+        #[allow(clippy::cast_possible_truncation)]
+        dwOSVersionInfoSize: std::mem::size_of::<OSVERSIONINFOW>() as u32,
+        ..Default::default()
+    };
+    unsafe { RtlGetVersion(&raw mut info) }
+        .is_ok()
+        .then_some(info.dwBuildNumber)
+}
+
+/// Whether the Game Bar is turned on in the settings, so `Win+G` opens it.
+///
+/// The value only exists once the setting has been changed, the Game Bar is on by default.
+pub fn is_game_bar_enabled() -> bool {
+    let mut enabled = 0u32;
+    // `size_of::<u32>()` will never exceed u32::MAX
+    #[allow(clippy::cast_possible_truncation)]
+    let mut len = std::mem::size_of::<u32>() as u32;
+    let result = unsafe {
+        RegGetValueW(
+            HKEY_CURRENT_USER,
+            &HSTRING::from(GAME_DVR_KEY),
+            w!("AppCaptureEnabled"),
+            RRF_RT_REG_DWORD,
+            None,
+            Some((&raw mut enabled).cast()),
+            Some(&raw mut len),
+        )
+    };
+    result.is_err() || enabled != 0
+}
+
+/// Whether a process is running from an executable called `exe`, e.g. `osk.exe`, ignoring case
+pub fn is_process_running(exe: &str) -> bool {
+    let Ok(snapshot) = (unsafe { CreateToolhelp32Snapshot(TH32CS_SNAPPROCESS, 0) }) else {
+        return false;
+    };
+    let mut entry = PROCESSENTRY32W {
+        // `size_of::<PROCESSENTRY32W>()` will never exceed u32::MAX
+        #[allow(clippy::cast_possible_truncation)]
+        dwSize: std::mem::size_of::<PROCESSENTRY32W>() as u32,
+        ..Default::default()
+    };
+    let mut found = false;
+    let mut next = unsafe { Process32FirstW(snapshot, &raw mut entry) };
+    while next.is_ok() {
+        let len = entry
+            .szExeFile
+            .iter()
+            .position(|&c| c == 0)
+            .unwrap_or(entry.szExeFile.len());
+        if String::from_utf16_lossy(&entry.szExeFile[..len]).eq_ignore_ascii_case(exe) {
+            found = true;
+            break;
+        }
+        next = unsafe { Process32NextW(snapshot, &raw mut entry) };
+    }
+    let _ = unsafe { CloseHandle(snapshot) };
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error;
+
+    use windows::Win32::Foundation::{ERROR_ACCESS_DENIED, E_FAIL};
+
+    use super::*;
+
+    #[test]
+    fn win32_keeps_the_win32_error_code() {
+        let error = windows::core::Error::from(ERROR_ACCESS_DENIED.to_hresult());
+        let error = ActionError::win32(&error, "lock the screen");
+        assert!(matches!(
+            &error,
+            ActionError::Win32Failed { code: 5, context } if context == "lock the screen"
+        ));
+
+        let message = error.to_string();
+        assert!(
+            message.starts_with("Failed to lock the screen: "),
+            "{message}"
+        );
+        assert!(message.ends_with(" (error 5)"), "{message}");
+    }
+
+    #[test]
+    fn win32_keeps_other_hresults_whole() {
+        let error = ActionError::win32(&windows::core::Error::from(E_FAIL), "toggle HDR");
+        assert!(matches!(
+            error,
+            ActionError::Win32Failed {
+                code: 0x8000_4005,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn com_keeps_the_hresult() {
+        let error = ActionError::com(&windows::core::Error::from(E_FAIL), "toggle mute");
+        assert!(matches!(
+            &error,
+            ActionError::ComFailed { hresult, context }
+                if *hresult == E_FAIL.0 && context == "toggle mute"
+        ));
+        assert!(error.to_string().ends_with("(0x80004005)"));
+    }
+
+    #[test]
+    fn describes_each_failure() {
+        let cases = [
+            (
+                ActionError::SendInputFailed {
+                    sent: 1,
+                    expected: 4,
+                },
+                "Sent 1 of 4 input events",
+            ),
+            (
+                ActionError::ShellExecuteFailed {
+                    uri: "osk.exe".to_owned(),
+                    error: 2,
+                },
+                "Failed to open osk.exe, ShellExecuteW returned 2",
+            ),
+            (
+                ActionError::NotFound {
+                    what: "on-screen keyboard window".to_owned(),
+                },
+                "Found no on-screen keyboard window",
+            ),
+            (
+                ActionError::UnknownFormat {
+                    what: "the Night Light state".to_owned(),
+                },
+                "Unknown format of the Night Light state",
+            ),
+            (
+                ActionError::ScriptFailed {
+                    path: Some(PathBuf::from(r"C:\corner.rhai")),
+                    message: "Variable not found: x".to_owned(),
+                },
+                r"The script C:\corner.rhai failed: Variable not found: x",
+            ),
+            (
+                ActionError::ScriptFailed {
+                    path: None,
+                    message: "Variable not found: x".to_owned(),
+                },
+                "The script failed: Variable not found: x",
+            ),
+            (
+                ActionError::PluginFailed {
+                    name: "x".to_owned(),
+                    code: 3,
+                },
+                "The x plugin returned 3",
+            ),
+            (
+                ActionError::TimedOut {
+                    what: "The x plugin".to_owned(),
+                    timeout: Duration::from_secs(5),
+                },
+                "The x plugin didn't finish within 5s",
+            ),
+        ];
+        for (error, message) in cases {
+            assert_eq!(error.to_string(), message);
+        }
+    }
+
+    #[test]
+    fn launch_failures_keep_their_source() {
+        let error = ActionError::ProcessLaunchFailed {
+            path: PathBuf::from("missing.exe"),
+            error: io::Error::from(io::ErrorKind::NotFound),
+        };
+        assert!(error
+            .to_string()
+            .starts_with("Failed to launch missing.exe: "));
+        assert!(error.source().is_some());
+        let error = ActionError::NotFound {
+            what: "display 2".to_owned(),
+        };
+        assert!(error.source().is_none());
+    }
+}
@@ -0,0 +1,142 @@
+//! Records every corner activation as a line of JSON in an optional log file
+
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+    sync::{
+        mpsc::{self, Receiver, Sender},
+        Mutex,
+    },
+    thread,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use serde::Serialize;
+use tracing::error;
+
+use crate::{config::ActionType, corners::Corner};
+
+/// Where entries currently go, `None` if logging is off
+static LOG: Mutex<Option<Log>> = Mutex::new(None);
+
+/// A running log writer
+#[derive(Debug)]
+struct Log {
+    /// File the writer appends to
+    path: PathBuf,
+    /// Size in bytes after which the file is rotated
+    max_size: u64,
+    /// Sends entries to the writer thread, which exits once this is dropped
+    sender: Sender<LogEntry>,
+}
+
+/// A single activation, one line in the log file
+#[derive(Debug, Serialize)]
+pub struct LogEntry {
+    /// When the corner activated, in milliseconds since the Unix epoch
+    pub ts_ms: u64,
+    /// Which corner activated
+    pub corner: Corner,
+    /// Type of the action that ran, e.g. `keystroke`
+    pub action: &'static str,
+    /// How long the cursor had to stay in the corner, in milliseconds
+    pub delay_ms: u64,
+}
+
+/// Starts writing entries to `path`, rotating it once it grows past `max_size` bytes, or stops
+/// logging if `path` is `None`. Does nothing if the settings didn't change.
+pub fn configure(path: Option<&Path>, max_size: u64) {
+    let mut log = LOG.lock().unwrap();
+    if let (Some(current), Some(path)) = (log.as_ref(), path) {
+        if current.path == path && current.max_size == max_size {
+            return;
+        }
+    }
+
+    // Dropping the previous sender stops its writer once it wrote everything that was sent
+    *log = path.map(|path| {
+        let (sender, receiver) = mpsc::channel();
+        let writer_path = path.to_path_buf();
+        thread::spawn(move || write_entries(&writer_path, max_size, &receiver));
+
+        Log {
+            path: path.to_path_buf(),
+            max_size,
+            sender,
+        }
+    });
+}
+
+/// Logs an activation of `corner`, without waiting for it to be written
+pub fn record(corner: Corner, action: &ActionType, delay: Duration) {
+    let Some(log) = &*LOG.lock().unwrap() else {
+        return;
+    };
+
+    let ts = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    let entry = LogEntry {
+        ts_ms: u64::try_from(ts.as_millis()).unwrap_or(u64::MAX),
+        corner,
+        action: action.name(),
+        delay_ms: u64::try_from(delay.as_millis()).unwrap_or(u64::MAX),
+    };
+    // The writer only goes away when logging is reconfigured
+    let _ = log.sender.send(entry);
+}
+
+/// Runs on the writer thread, appending every received entry to `path` until the sender is
+/// dropped
+fn write_entries(path: &Path, max_size: u64, receiver: &Receiver<LogEntry>) {
+    let mut file = None;
+    for entry in receiver {
+        if file.is_none() {
+            file = match open(path) {
+                Ok(opened) => Some(opened),
+                Err(e) => {
+                    error!("Failed to open {}: {e}", path.display());
+                    continue;
+                }
+            };
+        }
+        let Some((writer, size)) = file.as_mut() else {
+            continue;
+        };
+
+        let Ok(mut line) = serde_json::to_vec(&entry) else {
+            continue;
+        };
+        line.push(b'\n');
+        if let Err(e) = writer.write_all(&line).and_then(|()| writer.flush()) {
+            error!("Failed to write to {}: {e}", path.display());
+            continue;
+        }
+
+        *size += line.len() as u64;
+        if *size > max_size {
+            // Closed before renaming, it is reopened on the next entry
+            file = None;
+            if let Err(e) = rotate(path) {
+                error!("Failed to rotate {}: {e}", path.display());
+            }
+        }
+    }
+}
+
+/// Opens `path` for appending, returning the writer and the current size of the file
+fn open(path: &Path) -> io::Result<(BufWriter<File>, u64)> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    let file = OpenOptions::new().create(true).append(true).open(path)?;
+    let size = file.metadata()?.len();
+    Ok((BufWriter::new(file), size))
+}
+
+/// Moves `path` to e.g. `activations.1.jsonl`, replacing the previous one
+fn rotate(path: &Path) -> io::Result<()> {
+    fs::rename(path, path.with_extension("1.jsonl"))
+}
@@ -0,0 +1,197 @@
+//! Control of the default audio output device via the Core Audio API
+
+use windows::{
+    core::{Error, Result, GUID, HSTRING},
+    Win32::{
+        Devices::FunctionDiscovery::PKEY_Device_FriendlyName,
+        Foundation::E_INVALIDARG,
+        Media::Audio::{
+            eCommunications, eConsole, eMultimedia, eRender, Endpoints::IAudioEndpointVolume,
+            IMMDevice, IMMDeviceEnumerator, MMDeviceEnumerator, DEVICE_STATE_ACTIVE,
+        },
+        System::Com::{CoCreateInstance, CoTaskMemFree, CLSCTX_ALL, STGM_READ},
+    },
+};
+
+use policy_config::{set_default_endpoint, IPolicyConfig};
+
+/// `CPolicyConfigClient`, the undocumented class Windows' own sound settings use to change the
+/// default device. Its interface has been stable since Windows 7.
+const POLICY_CONFIG_CLIENT: GUID = GUID::from_u128(0x870a_f99c_171d_4f9e_af0d_e63d_f40c_2bc9);
+
+/// Declaration of `IPolicyConfig`, whose generated code doesn't follow our lints
+#[allow(non_snake_case, clippy::transmute_ptr_to_ptr)]
+mod policy_config {
+    use windows::core::{interface, IUnknown, IUnknown_Vtbl, Result, HRESULT, HSTRING, PCWSTR};
+    use windows::Win32::Media::Audio::ERole;
+
+    /// Interface of `POLICY_CONFIG_CLIENT`. Only `SetDefaultEndpoint` is used, the other
+    /// methods are declared to get the vtable layout right.
+    #[interface("f8679f50-850a-41cf-9c72-430f290290c8")]
+    pub unsafe trait IPolicyConfig: IUnknown {
+        fn GetMixFormat(&self, device: PCWSTR, format: *mut *mut core::ffi::c_void) -> HRESULT;
+        fn GetDeviceFormat(
+            &self,
+            device: PCWSTR,
+            default: i32,
+            format: *mut *mut core::ffi::c_void,
+        ) -> HRESULT;
+        fn ResetDeviceFormat(&self, device: PCWSTR) -> HRESULT;
+        fn SetDeviceFormat(
+            &self,
+            device: PCWSTR,
+            endpoint_format: *mut core::ffi::c_void,
+            mix_format: *mut core::ffi::c_void,
+        ) -> HRESULT;
+        fn GetProcessingPeriod(
+            &self,
+            device: PCWSTR,
+            default: i32,
+            default_period: *mut i64,
+            minimum_period: *mut i64,
+        ) -> HRESULT;
+        fn SetProcessingPeriod(&self, device: PCWSTR, period: *mut i64) -> HRESULT;
+        fn GetShareMode(&self, device: PCWSTR, mode: *mut core::ffi::c_void) -> HRESULT;
+        fn SetShareMode(&self, device: PCWSTR, mode: *mut core::ffi::c_void) -> HRESULT;
+        fn GetPropertyValue(
+            &self,
+            device: PCWSTR,
+            store: i32,
+            key: *const core::ffi::c_void,
+            value: *mut core::ffi::c_void,
+        ) -> HRESULT;
+        fn SetPropertyValue(
+            &self,
+            device: PCWSTR,
+            store: i32,
+            key: *const core::ffi::c_void,
+            value: *mut core::ffi::c_void,
+        ) -> HRESULT;
+        fn SetDefaultEndpoint(&self, device: PCWSTR, role: ERole) -> HRESULT;
+        fn SetEndpointVisibility(&self, device: PCWSTR, visible: i32) -> HRESULT;
+    }
+
+    /// Makes the endpoint with the ID `device` the default one for `role`
+    pub unsafe fn set_default_endpoint(
+        policy: &IPolicyConfig,
+        device: &HSTRING,
+        role: ERole,
+    ) -> Result<()> {
+        policy
+            .SetDefaultEndpoint(PCWSTR(device.as_ptr()), role)
+            .ok()
+    }
+}
+
+/// Mutes the default output device, or unmutes it if it is muted.
+///
+/// COM has to be initialized on the calling thread.
+pub fn toggle_mute() -> Result<()> {
+    unsafe {
+        let volume = default_endpoint_volume()?;
+        let muted = volume.GetMute()?.as_bool();
+        volume.SetMute(!muted, std::ptr::null())
+    }
+}
+
+/// Changes the volume of the default output device by `delta`, clamped to the valid range of
+/// 0.0 to 1.0.
+///
+/// COM has to be initialized on the calling thread.
+pub fn change_volume(delta: f32) -> Result<()> {
+    unsafe {
+        let volume = default_endpoint_volume()?;
+        let level = adjust_level(volume.GetMasterVolumeLevelScalar()?, delta);
+        volume.SetMasterVolumeLevelScalar(level, std::ptr::null())
+    }
+}
+
+/// Makes the output device after the current default one the default, wrapping around, and
+/// returns its name. With `names`, only the devices with one of these names are cycled through,
+/// ignoring case.
+///
+/// COM has to be initialized on the calling thread.
+pub fn cycle_output_device(names: Option<&[String]>) -> Result<String> {
+    unsafe {
+        let enumerator: IMMDeviceEnumerator =
+            CoCreateInstance(&MMDeviceEnumerator, None, CLSCTX_ALL)?;
+        let current = device_id(&enumerator.GetDefaultAudioEndpoint(eRender, eConsole)?)?;
+
+        let collection = enumerator.EnumAudioEndpoints(eRender, DEVICE_STATE_ACTIVE)?;
+        let mut devices = Vec::new();
+        for i in 0..collection.GetCount()? {
+            let device = collection.Item(i)?;
+            let name = friendly_name(&device)?;
+            if names.is_none_or(|names| names.iter().any(|n| n.eq_ignore_ascii_case(&name))) {
+                devices.push((device_id(&device)?, name));
+            }
+        }
+        if devices.is_empty() {
+            return Err(Error::new(
+                E_INVALIDARG,
+                "No matching output device is active",
+            ));
+        }
+
+        let next = devices
+            .iter()
+            .position(|(id, _)| *id == current)
+            .map_or(0, |i| (i + 1) % devices.len());
+        let (id, name) = devices.swap_remove(next);
+        let policy: IPolicyConfig = CoCreateInstance(&POLICY_CONFIG_CLIENT, None, CLSCTX_ALL)?;
+        let id = HSTRING::from(id);
+        for role in [eConsole, eMultimedia, eCommunications] {
+            set_default_endpoint(&policy, &id, role)?;
+        }
+        Ok(name)
+    }
+}
+
+/// Endpoint ID of `device`
+unsafe fn device_id(device: &IMMDevice) -> Result<String> {
+    let id = device.GetId()?;
+    let string = String::from_utf16_lossy(id.as_wide());
+    CoTaskMemFree(Some(id.0.cast()));
+    Ok(string)
+}
+
+/// Name of `device` as shown in the sound settings, e.g. `Speakers (Realtek(R) Audio)`
+unsafe fn friendly_name(device: &IMMDevice) -> Result<String> {
+    let properties = device.OpenPropertyStore(STGM_READ)?;
+    Ok(properties.GetValue(&PKEY_Device_FriendlyName)?.to_string())
+}
+
+/// Adds `delta` to the volume `level`, clamping the result to 0.0 to 1.0
+fn adjust_level(level: f32, delta: f32) -> f32 {
+    (level + delta).clamp(0.0, 1.0)
+}
+
+/// Volume control of the default output device
+unsafe fn default_endpoint_volume() -> Result<IAudioEndpointVolume> {
+    let enumerator: IMMDeviceEnumerator = CoCreateInstance(&MMDeviceEnumerator, None, CLSCTX_ALL)?;
+    let device = enumerator.GetDefaultAudioEndpoint(eRender, eConsole)?;
+    device.Activate(CLSCTX_ALL, None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::adjust_level;
+
+    #[test]
+    fn adjusts_within_range() {
+        assert!((adjust_level(0.5, 0.05) - 0.55).abs() < f32::EPSILON);
+        assert!((adjust_level(0.5, -0.05) - 0.45).abs() < f32::EPSILON);
+    }
+
+    #[test]
+    fn clamps_at_full_volume() {
+        assert!((adjust_level(0.98, 0.05) - 1.0).abs() < f32::EPSILON);
+        assert!((adjust_level(1.0, 0.05) - 1.0).abs() < f32::EPSILON);
+    }
+
+    #[test]
+    fn clamps_at_silence() {
+        assert!(adjust_level(0.02, -0.05).abs() < f32::EPSILON);
+        assert!(adjust_level(0.0, -1.0).abs() < f32::EPSILON);
+    }
+}
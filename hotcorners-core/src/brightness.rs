@@ -0,0 +1,120 @@
+//! Display brightness through WMI, which only built-in panels of laptops and tablets support
+
+use windows::{
+    core::{w, Error, Result, BSTR, HRESULT, VARIANT},
+    Win32::System::{
+        Com::{CoCreateInstance, CLSCTX_INPROC_SERVER},
+        Wmi::{
+            IWbemClassObject, IWbemContext, IWbemLocator, IWbemServices, WbemLocator,
+            WBEM_E_NOT_SUPPORTED, WBEM_FLAG_FORWARD_ONLY, WBEM_FLAG_RETURN_IMMEDIATELY,
+            WBEM_GENERIC_FLAG_TYPE, WBEM_INFINITE,
+        },
+    },
+};
+
+use crate::com::ComGuard;
+
+/// Query for the brightness of the active built-in display
+const BRIGHTNESS_QUERY: &str =
+    "SELECT CurrentBrightness FROM WmiMonitorBrightness WHERE Active = TRUE";
+/// Query for the object to set the brightness of the active built-in display with
+const METHODS_QUERY: &str = "SELECT * FROM WmiMonitorBrightnessMethods WHERE Active = TRUE";
+
+/// Changes the brightness of the built-in display by `delta` percentage points, clamped to 0 to
+/// 100.
+///
+/// COM has to be initialized on the calling thread.
+pub fn change_brightness(delta: i32) -> Result<()> {
+    unsafe {
+        let services = connect()?;
+
+        let current = first_object(&services, BRIGHTNESS_QUERY)?.ok_or_else(not_supported)?;
+        let mut value = VARIANT::default();
+        current.Get(w!("CurrentBrightness"), 0, &raw mut value, None, None)?;
+        let level = i64::from(u32::try_from(&value)?);
+        // Clamped to 0 to 100
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        let target = (level + i64::from(delta)).clamp(0, 100) as u8;
+
+        let methods = first_object(&services, METHODS_QUERY)?.ok_or_else(not_supported)?;
+        let mut path = VARIANT::default();
+        methods.Get(w!("__PATH"), 0, &raw mut path, None, None)?;
+        let path = BSTR::try_from(&path)?;
+
+        let mut class = None;
+        services.GetObject(
+            &BSTR::from("WmiMonitorBrightnessMethods"),
+            WBEM_GENERIC_FLAG_TYPE(0),
+            None::<&IWbemContext>,
+            Some(&raw mut class),
+            None,
+        )?;
+        let mut signature = None;
+        class.ok_or_else(not_supported)?.GetMethod(
+            w!("WmiSetBrightness"),
+            0,
+            &raw mut signature,
+            std::ptr::null_mut(),
+        )?;
+        let params = signature.ok_or_else(not_supported)?.SpawnInstance(0)?;
+        // WMI expects `uint32` parameters as `VT_I4`
+        params.Put(w!("Timeout"), 0, &VARIANT::from(0i32), 0)?;
+        params.Put(w!("Brightness"), 0, &VARIANT::from(target), 0)?;
+
+        services.ExecMethod(
+            &path,
+            &BSTR::from("WmiSetBrightness"),
+            WBEM_GENERIC_FLAG_TYPE(0),
+            None::<&IWbemContext>,
+            &params,
+            None,
+            None,
+        )
+    }
+}
+
+/// Whether any display's brightness can be changed, usually `false` on desktops
+pub fn is_supported() -> bool {
+    // WMI works just as well if COM was already initialized in another apartment
+    let _com = ComGuard::new();
+    unsafe { connect().and_then(|services| first_object(&services, BRIGHTNESS_QUERY)) }
+        .is_ok_and(|object| object.is_some())
+}
+
+/// Connects to the `root\wmi` namespace, which has the monitor classes
+unsafe fn connect() -> Result<IWbemServices> {
+    let locator: IWbemLocator = CoCreateInstance(&WbemLocator, None, CLSCTX_INPROC_SERVER)?;
+    locator.ConnectServer(
+        &BSTR::from(r"root\wmi"),
+        &BSTR::new(),
+        &BSTR::new(),
+        &BSTR::new(),
+        0,
+        &BSTR::new(),
+        None::<&IWbemContext>,
+    )
+}
+
+/// The first object `query` finds, if any
+unsafe fn first_object(services: &IWbemServices, query: &str) -> Result<Option<IWbemClassObject>> {
+    let objects = services.ExecQuery(
+        &BSTR::from("WQL"),
+        &BSTR::from(query),
+        WBEM_GENERIC_FLAG_TYPE(WBEM_FLAG_FORWARD_ONLY.0 | WBEM_FLAG_RETURN_IMMEDIATELY.0),
+        None::<&IWbemContext>,
+    )?;
+    let mut object = [None];
+    let mut returned = 0;
+    objects
+        .Next(WBEM_INFINITE, &mut object, &raw mut returned)
+        .ok()?;
+    Ok(object[0].take())
+}
+
+/// Error for displays that don't support brightness control
+fn not_supported() -> Error {
+    Error::new(
+        HRESULT(WBEM_E_NOT_SUPPORTED.0),
+        "No display supports brightness control through WMI",
+    )
+}
@@ -0,0 +1,38 @@
+//! COM initialization for threads that call COM APIs
+
+use std::marker::PhantomData;
+
+use windows::Win32::System::Com::{CoInitializeEx, CoUninitialize, COINIT_APARTMENTTHREADED};
+
+/// Keeps COM initialized on the current thread and uninitializes it again when dropped.
+///
+/// Created at the top of a thread that runs actions using COM, so a thread that is replaced
+/// doesn't leak its COM state. It can't be sent to another thread, `CoUninitialize` has to be
+/// called on the thread that initialized COM.
+#[derive(Debug)]
+pub struct ComGuard {
+    /// Keeps the guard on its thread
+    _not_send: PhantomData<*const ()>,
+}
+
+impl ComGuard {
+    /// Initializes COM as a single-threaded apartment.
+    ///
+    /// Fails with `RPC_E_CHANGED_MODE` if the thread is already in a multithreaded apartment,
+    /// rather than running the actions in an apartment they don't expect.
+    pub fn new() -> windows::core::Result<Self> {
+        // `S_FALSE` if COM already was a single-threaded apartment, which still has to be
+        // balanced by `CoUninitialize`
+        unsafe { CoInitializeEx(None, COINIT_APARTMENTTHREADED) }.ok()?;
+        Ok(Self {
+            _not_send: PhantomData,
+        })
+    }
+}
+
+impl Drop for ComGuard {
+    fn drop(&mut self) {
+        // Only guards whose initialization succeeded exist
+        unsafe { CoUninitialize() };
+    }
+}
@@ -0,0 +1,2024 @@
+//! Loading of the optional `config.toml` configuration file
+
+use std::{
+    env, fmt, fs, io,
+    path::{Path, PathBuf},
+    sync::OnceLock,
+    time::Duration,
+};
+
+use serde::{
+    de::{
+        value::{MapAccessDeserializer, MapDeserializer, SeqAccessDeserializer},
+        MapAccess, SeqAccess, Visitor,
+    },
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+use tracing::{info, warn};
+use windows::Win32::UI::{
+    Input::KeyboardAndMouse::{
+        HOT_KEY_MODIFIERS, KEYEVENTF_KEYUP, MOD_ALT, MOD_CONTROL, MOD_SHIFT, MOD_WIN, VIRTUAL_KEY,
+        VK_BACK, VK_CONTROL, VK_DELETE, VK_DOWN, VK_END, VK_ESCAPE, VK_F1, VK_HOME, VK_INSERT,
+        VK_LCONTROL, VK_LEFT, VK_LMENU, VK_LSHIFT, VK_LWIN, VK_MENU, VK_NEXT, VK_PRIOR,
+        VK_RCONTROL, VK_RETURN, VK_RIGHT, VK_RMENU, VK_RSHIFT, VK_RWIN, VK_SHIFT, VK_SPACE, VK_TAB,
+        VK_UP,
+    },
+    WindowsAndMessaging::{GetSystemMetrics, SM_TABLETPC},
+};
+
+#[cfg(feature = "scripting")]
+use crate::scripting;
+use crate::{
+    actions::{
+        is_game_bar_enabled, url_scheme, windows_build, COPILOT_MIN_BUILD, DEFAULT_URL_SCHEMES,
+        SETTINGS_PAGES, WINDOWS_11_BUILD,
+    },
+    corners::{Corner, Direction, ZoneShape, DEFAULT_CORNER_SIZE, DEFAULT_DPI},
+    display::{self, DisplayProjectionMode, ROTATIONS},
+    monitors::MonitorInfo,
+    window::{HorizontalDirection, WindowStateAction},
+};
+
+/// Directory inside `%APPDATA%` that holds the configuration file
+const CONFIG_DIR: &str = "hotcorners";
+/// Name of the configuration file
+const CONFIG_FILE: &str = "config.toml";
+
+/// Longest `delay_ms` a corner accepts. The corner's worker sleeps through the delay, and
+/// entries in the meantime can't arm it again.
+const MAX_DELAY_MS: u64 = 10_000;
+
+/// Commented configuration with every option at its default, written by `--init`
+const DEFAULT_CONFIG: &str = include_str!("default_config.toml");
+
+/// Configuration file given with `--config`, replacing the default location
+static CONFIG_PATH: OnceLock<PathBuf> = OnceLock::new();
+
+/// Top-level configuration
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+// Every bool is an independent on/off switch in `config.toml`
+#[allow(clippy::struct_excessive_bools)]
+pub struct Config {
+    /// Hot corners to activate. Defaults to just the top-left corner.
+    #[serde(default = "default_corners")]
+    pub corners: Vec<CornerConfig>,
+    /// Hot zones along whole screen edges, or any other position, active alongside `corners`.
+    /// They take the same options as `corners`.
+    #[serde(default)]
+    pub zones: Vec<CornerConfig>,
+    /// Size of every hot corner in pixels at 100% scaling, unless overridden per corner
+    pub corner_size: Option<u32>,
+    /// Don't activate corners while the foreground window is fullscreen
+    #[serde(default = "default_suppress_fullscreen")]
+    pub suppress_fullscreen: bool,
+    /// Don't activate corners while a window of one of these processes is in the foreground, e.g.
+    /// `dota2.exe`
+    #[serde(default)]
+    pub suppress_for: Vec<String>,
+    /// Don't activate corners while a window with one of these window classes is in the
+    /// foreground, e.g. `UnityWndClass`. Cheaper to check than `suppress_for`.
+    #[serde(default)]
+    pub suppress_for_class: Vec<String>,
+    /// Don't activate corners for mouse moves that were injected or synthesized from touch or pen
+    /// input, e.g. when swiping near the edge of a touchscreen
+    #[serde(default)]
+    pub suppress_touch: bool,
+    /// Don't activate corners while the system runs on battery
+    #[serde(default)]
+    pub disable_on_battery: bool,
+    /// Don't activate corners while hotcorners runs in a Remote Desktop session
+    #[serde(default = "default_suppress_in_remote_desktop")]
+    pub suppress_in_remote_desktop: bool,
+    /// Named sets of corners to switch between. If there are any, the active profile's corners
+    /// are used instead of `corners`.
+    #[serde(default)]
+    pub profiles: Vec<Profile>,
+    /// Name of the profile that is active at startup, defaults to the first one
+    pub default_profile: Option<String>,
+    /// Hotkey that switches to the next profile
+    pub profile_cycle_hotkey: Option<HotkeyConfig>,
+    /// Hotkey that exits hotcorners, Ctrl+Alt+C if not set
+    pub exit_hotkey: Option<HotkeyConfig>,
+    /// Try other combinations for the exit hotkey if another program already uses it
+    #[serde(default)]
+    pub auto_fallback_hotkey: bool,
+    /// File to append a line of JSON to on every activation
+    pub log_file: Option<PathBuf>,
+    /// Size in megabytes after which `log_file` is moved aside and started over
+    #[serde(default = "default_max_log_size_mb")]
+    pub max_log_size_mb: u64,
+    /// URL schemes the `url` action may open besides `http` and `https`, e.g. `mailto`
+    #[serde(default)]
+    pub allowed_schemes: Vec<String>,
+    /// DLLs to load `plugin` action types from, needs the `plugins` feature
+    #[serde(default)]
+    pub plugin_paths: Vec<PathBuf>,
+    /// Show a tray notification when a corner's action fails, besides logging it
+    #[serde(default)]
+    pub notify_on_action_error: bool,
+    /// Send the rest of an action's input events again if `SendInput` didn't send all of them,
+    /// e.g. because another program holds the foreground lock
+    #[serde(default)]
+    pub retry_on_failure: bool,
+    /// How many times `retry_on_failure` sends the input again before giving up
+    #[serde(default = "default_max_retries")]
+    pub max_retries: u32,
+    /// How long `retry_on_failure` waits before each retry, in milliseconds
+    #[serde(default = "default_retry_delay_ms")]
+    pub retry_delay_ms: u64,
+    /// Fields the system-wide configuration locked, the user's configuration can't change them
+    #[serde(skip)]
+    pub locked: Vec<String>,
+}
+
+/// A configuration file that only sets some of the fields of `Config`, layered over the
+/// system-wide configuration or the defaults by `merge_configs`.
+///
+/// See `Config` for what the fields mean.
+#[allow(missing_docs)]
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PartialConfig {
+    pub corners: Option<Vec<CornerConfig>>,
+    pub zones: Option<Vec<CornerConfig>>,
+    pub corner_size: Option<u32>,
+    pub suppress_fullscreen: Option<bool>,
+    pub suppress_for: Option<Vec<String>>,
+    pub suppress_for_class: Option<Vec<String>>,
+    pub suppress_touch: Option<bool>,
+    pub disable_on_battery: Option<bool>,
+    pub suppress_in_remote_desktop: Option<bool>,
+    pub profiles: Option<Vec<Profile>>,
+    pub default_profile: Option<String>,
+    pub profile_cycle_hotkey: Option<HotkeyConfig>,
+    pub exit_hotkey: Option<HotkeyConfig>,
+    pub auto_fallback_hotkey: Option<bool>,
+    pub log_file: Option<PathBuf>,
+    pub max_log_size_mb: Option<u64>,
+    pub allowed_schemes: Option<Vec<String>>,
+    pub plugin_paths: Option<Vec<PathBuf>>,
+    pub notify_on_action_error: Option<bool>,
+    pub retry_on_failure: Option<bool>,
+    pub max_retries: Option<u32>,
+    pub retry_delay_ms: Option<u64>,
+    /// Fields later layers can't override, only allowed in the system-wide configuration
+    pub locked: Option<LockedFields>,
+}
+
+/// The `[locked]` section of the system-wide configuration
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LockedFields {
+    /// Names of the locked fields, e.g. `suppress_fullscreen`
+    pub fields: Vec<String>,
+}
+
+/// Names of the fields of `Config` that can be locked
+const FIELDS: &[&str] = &[
+    "corners",
+    "zones",
+    "corner_size",
+    "suppress_fullscreen",
+    "suppress_for",
+    "suppress_for_class",
+    "suppress_touch",
+    "disable_on_battery",
+    "suppress_in_remote_desktop",
+    "profiles",
+    "default_profile",
+    "profile_cycle_hotkey",
+    "exit_hotkey",
+    "auto_fallback_hotkey",
+    "log_file",
+    "max_log_size_mb",
+    "allowed_schemes",
+    "plugin_paths",
+    "notify_on_action_error",
+    "retry_on_failure",
+    "max_retries",
+    "retry_delay_ms",
+];
+
+/// A named set of corners, one `[[profiles]]` entry
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Profile {
+    /// Name shown when switching to the profile
+    pub name: String,
+    /// Hot corners to activate while the profile is active, none if empty
+    #[serde(default)]
+    pub corners: Vec<CornerConfig>,
+    /// Hot zones to activate alongside `corners` while the profile is active
+    #[serde(default)]
+    pub zones: Vec<CornerConfig>,
+}
+
+/// A key combination to register as a global hotkey, e.g. `{ key = "P", modifiers = ["ctrl",
+/// "alt"] }`
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct HotkeyConfig {
+    /// Name of the key, e.g. `P`, `F9` or `Space`
+    pub key: String,
+    /// Any of `ctrl`, `alt`, `shift` and `win`
+    #[serde(default)]
+    pub modifiers: Vec<String>,
+}
+
+/// Configuration for a single hot corner, one `[[corners]]` entry
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct CornerConfig {
+    /// Which corner of the screen this entry applies to
+    pub position: Corner,
+    /// Set to `false` to keep the entry around without activating the corner
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// Size of this corner in pixels at 100% scaling, overriding `Config::corner_size`
+    pub size: Option<u32>,
+    /// `"triangle"` to only activate close to the very corner instead of anywhere in the square
+    #[serde(default)]
+    pub shape: ZoneShape,
+    /// How long the cursor has to stay in the corner before it activates, in milliseconds
+    pub delay_ms: Option<u64>,
+    /// Ignore entries faster than this many pixels per millisecond, e.g. when sweeping the cursor
+    /// towards the taskbar
+    pub max_entry_velocity: Option<f64>,
+    /// Only activate when the cursor enters the corner from one of these directions, e.g.
+    /// `["from-right", "from-below"]` to ignore the cursor running up the left edge of the screen
+    #[serde(default)]
+    pub entry_directions: Vec<Direction>,
+    /// How long the corner stays inactive after it activated, in milliseconds
+    #[serde(default = "default_cooldown_ms")]
+    pub cooldown_ms: u64,
+    /// What to do on activation, defaults to sending `Win+Tab`
+    #[serde(
+        default = "default_action",
+        deserialize_with = "deserialize_action",
+        serialize_with = "serialize_action"
+    )]
+    pub action: ActionType,
+}
+
+/// What a corner does when it activates.
+///
+/// In `config.toml` a list of keys is a `Keystroke`, every other action is a table with a
+/// `type`, e.g. `{ type = "exec", path = "C:\\Windows\\notepad.exe" }`. Actions without
+/// parameters can also be given as just their type, e.g. `"lock-screen"`.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "kebab-case", deny_unknown_fields)]
+pub enum ActionType {
+    /// Sends a key combination
+    #[serde(skip)]
+    Keystroke(Vec<KeyEvent>),
+    /// Launches a program, detached from hotcorners
+    Exec {
+        /// Program to launch
+        path: PathBuf,
+        /// Command-line arguments for the program
+        #[serde(default)]
+        args: Vec<String>,
+    },
+    /// Locks the workstation
+    LockScreen,
+    /// Minimizes every window to show the desktop, or restores them, like `Win+D`
+    ShowDesktop,
+    /// Switches to the virtual desktop on the left, like `Win+Ctrl+Left`
+    SwitchDesktopLeft,
+    /// Switches to the virtual desktop on the right, like `Win+Ctrl+Right`
+    SwitchDesktopRight,
+    /// Creates a new virtual desktop and switches to it, like `Win+Ctrl+D`
+    NewDesktop,
+    /// Closes the current virtual desktop, like `Win+Ctrl+F4`
+    CloseDesktop,
+    /// Mutes or unmutes the default audio output device. Like the volume actions, needs the
+    /// `audio` feature.
+    MuteToggle,
+    /// Raises the volume of the default audio output device
+    VolumeUp {
+        /// How much to raise the volume by, from 0.0 to 1.0
+        #[serde(default = "default_volume_step")]
+        amount: f32,
+    },
+    /// Lowers the volume of the default audio output device
+    VolumeDown {
+        /// How much to lower the volume by, from 0.0 to 1.0
+        #[serde(default = "default_volume_step")]
+        amount: f32,
+    },
+    /// Makes the next active audio output device the default, wrapping around
+    CycleAudioDevice {
+        /// Only cycle through the devices with these names, e.g. `Speakers (Realtek(R) Audio)`
+        devices: Option<Vec<String>>,
+    },
+    /// Captures every monitor and saves the result as a PNG file
+    Screenshot {
+        /// Directory to save the screenshot in, defaults to `%USERPROFILE%\Pictures\Screenshots`
+        save_path: Option<PathBuf>,
+        /// Also copy the screenshot to the clipboard
+        #[serde(default)]
+        clipboard: bool,
+    },
+    /// Types a text into the focused window, e.g. an email signature
+    TypeText {
+        /// Text to type, may contain any Unicode characters
+        text: String,
+    },
+    /// Brings a window to the foreground, or minimizes it if it already is, like a drop-down
+    /// terminal
+    ToggleWindow {
+        /// Window class name of the window
+        class: Option<String>,
+        /// Part of the window's title, ignoring case
+        title_contains: Option<String>,
+        /// Program to launch if there is no such window
+        exec: Option<PathBuf>,
+        /// Command-line arguments for `exec`
+        #[serde(default)]
+        args: Vec<String>,
+    },
+    /// Moves the foreground window to the neighbouring monitor and makes it fill the work area
+    MoveWindowToMonitor {
+        /// Which neighbour to move the window to
+        direction: HorizontalDirection,
+        /// Move the window from the last monitor to the first one on the other side
+        #[serde(default)]
+        wrap: bool,
+    },
+    /// Opens a URL with its default program, usually the browser
+    #[serde(rename = "url")]
+    OpenUrl {
+        /// URL to open. Non-ASCII characters are percent-encoded.
+        url: String,
+    },
+    /// Opens the Copilot sidebar, like `Win+C`. Needs Windows 11.
+    Copilot,
+    /// Turns auto-hiding of the taskbar on or off
+    ToggleTaskbarAutohide,
+    /// Changes the brightness of the built-in display. Needs the `wmi` feature.
+    Brightness {
+        /// Percentage points to change the brightness by, negative to dim it
+        delta: i32,
+    },
+    /// Turns the mouse wheel, scrolling the window under the cursor
+    #[serde(rename = "scroll")]
+    ScrollWheel {
+        /// Notches to turn the wheel by, positive scrolls up and negative down
+        delta: i32,
+    },
+    /// Opens a page of the Settings app
+    Settings {
+        /// Page to open, the part of its `ms-settings:` URI after the colon, e.g. `display`
+        page: String,
+    },
+    /// Switches between using every display, mirroring them or only some, like `Win+P`
+    DisplayMode {
+        /// How to use the displays
+        mode: DisplayProjectionMode,
+    },
+    /// Turns HDR on the primary display on or off
+    ToggleHdr,
+    /// Rotates a display, e.g. to portrait on a convertible
+    RotateDisplay {
+        /// Clockwise rotation from the natural orientation: 0, 90, 180 or 270 degrees
+        degrees: u32,
+        /// Which display to rotate, counting from 0 in the order Windows lists them. Defaults to
+        /// the primary display.
+        monitor_index: Option<u32>,
+    },
+    /// Switches a display to another resolution, e.g. for presenting
+    SetResolution {
+        /// Width in pixels
+        width: u32,
+        /// Height in pixels
+        height: u32,
+        /// GDI device name of the display, e.g. `\\.\DISPLAY2`. Defaults to the primary display.
+        monitor_name: Option<String>,
+        /// Switch back to the previous resolution after this many seconds, instead of keeping
+        /// the new one
+        revert_after_seconds: Option<u64>,
+    },
+    /// Runs a Rhai script. Needs the `scripting` feature.
+    #[serde(rename = "rhai")]
+    Script {
+        /// The script itself
+        script: Option<String>,
+        /// File to read the script from instead, read again on every activation
+        path: Option<PathBuf>,
+    },
+    /// Shows or hides the desktop icons
+    ToggleDesktopIcons,
+    /// Opens the Run dialog, like `Win+R`
+    RunDialog,
+    /// Opens Windows Search, like `Win+S`
+    Search,
+    /// Opens the Xbox Game Bar, like `Win+G`
+    GameBar,
+    /// Turns Night Light on or off
+    NightLight {
+        /// Rewrite its state in the registry instead of opening its Settings page
+        #[serde(default)]
+        use_registry: bool,
+    },
+    /// Turns Focus Assist, or Do Not Disturb on Windows 11, on or off. Needs the `focus-assist`
+    /// feature.
+    FocusAssist,
+    /// Shows or hides the on-screen keyboard, starting it if it isn't running
+    OnScreenKeyboard,
+    /// Opens the Windows Mobility Center
+    MobilityCenter,
+    /// Closes the foreground window
+    CloseWindow {
+        /// Ask before closing it
+        #[serde(default)]
+        confirm: bool,
+    },
+    /// Maximizes, minimizes or restores the foreground window
+    WindowState {
+        /// What to do with the window
+        state: WindowStateAction,
+    },
+    /// Sends key presses and releases exactly in the configured order
+    Keys {
+        /// The events to send
+        sequence: Vec<KeySequenceEvent>,
+    },
+    /// Runs an action type provided by a plugin DLL. Needs the `plugins` feature.
+    Plugin {
+        /// Name the plugin gives its action type
+        name: String,
+    },
+}
+
+/// A single key press or release of a `keys` action
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct KeySequenceEvent {
+    /// The key, e.g. `0x5B` or `"VK_LWIN"`
+    pub vk: KeyCode,
+    /// Whether the key is pressed, rather than released
+    pub down: bool,
+    /// Additional `KEYBD_EVENT_FLAGS`, e.g. `1` for `KEYEVENTF_EXTENDEDKEY`
+    #[serde(default)]
+    pub flags: u32,
+}
+
+/// A virtual key code, or the name of one
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum KeyCode {
+    /// The code itself, e.g. `0x09`
+    Code(u16),
+    /// A name like `"VK_TAB"`, `"Tab"` or `"A"`, ignoring case
+    Name(String),
+}
+
+impl KeyCode {
+    /// The virtual key, `None` for unknown names
+    pub fn resolve(&self) -> Option<VIRTUAL_KEY> {
+        match self {
+            KeyCode::Code(vk) => Some(VIRTUAL_KEY(*vk)),
+            KeyCode::Name(name) => parse_vk_name(name),
+        }
+    }
+}
+
+/// A single key of the combination sent when a corner activates
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct KeyEvent {
+    /// Virtual key code, e.g. `0x5B` for the left Windows key
+    pub vk: u16,
+    /// Additional `KEYBD_EVENT_FLAGS` for both the key down and key up events, e.g. `1` for
+    /// `KEYEVENTF_EXTENDEDKEY`
+    #[serde(default)]
+    pub flags: u32,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            corners: default_corners(),
+            zones: Vec::new(),
+            corner_size: None,
+            suppress_fullscreen: default_suppress_fullscreen(),
+            suppress_for: Vec::new(),
+            suppress_for_class: Vec::new(),
+            suppress_touch: false,
+            disable_on_battery: false,
+            suppress_in_remote_desktop: default_suppress_in_remote_desktop(),
+            profiles: Vec::new(),
+            default_profile: None,
+            profile_cycle_hotkey: None,
+            exit_hotkey: None,
+            auto_fallback_hotkey: false,
+            log_file: None,
+            max_log_size_mb: default_max_log_size_mb(),
+            allowed_schemes: Vec::new(),
+            plugin_paths: Vec::new(),
+            notify_on_action_error: false,
+            retry_on_failure: false,
+            max_retries: default_max_retries(),
+            retry_delay_ms: default_retry_delay_ms(),
+            locked: Vec::new(),
+        }
+    }
+}
+
+impl ActionType {
+    /// Cargo feature this build was compiled without that the action needs, if any. Such actions
+    /// still load so configurations can be shared between builds, but do nothing.
+    pub fn missing_feature(&self) -> Option<&'static str> {
+        match self {
+            ActionType::MuteToggle
+            | ActionType::VolumeUp { .. }
+            | ActionType::VolumeDown { .. }
+            | ActionType::CycleAudioDevice { .. }
+                if !cfg!(feature = "audio") =>
+            {
+                Some("audio")
+            }
+            ActionType::Brightness { .. } if !cfg!(feature = "wmi") => Some("wmi"),
+            ActionType::Script { .. } if !cfg!(feature = "scripting") => Some("scripting"),
+            ActionType::FocusAssist if !cfg!(feature = "focus-assist") => Some("focus-assist"),
+            ActionType::Plugin { .. } if !cfg!(feature = "plugins") => Some("plugins"),
+            _ => None,
+        }
+    }
+
+    /// The action's `type` in `config.toml`, or `keystroke` for a list of keys
+    pub fn name(&self) -> &'static str {
+        match self {
+            ActionType::Keystroke(_) => "keystroke",
+            ActionType::Exec { .. } => "exec",
+            ActionType::LockScreen => "lock-screen",
+            ActionType::ShowDesktop => "show-desktop",
+            ActionType::SwitchDesktopLeft => "switch-desktop-left",
+            ActionType::SwitchDesktopRight => "switch-desktop-right",
+            ActionType::NewDesktop => "new-desktop",
+            ActionType::CloseDesktop => "close-desktop",
+            ActionType::MuteToggle => "mute-toggle",
+            ActionType::VolumeUp { .. } => "volume-up",
+            ActionType::VolumeDown { .. } => "volume-down",
+            ActionType::CycleAudioDevice { .. } => "cycle-audio-device",
+            ActionType::Screenshot { .. } => "screenshot",
+            ActionType::TypeText { .. } => "type-text",
+            ActionType::ToggleWindow { .. } => "toggle-window",
+            ActionType::MoveWindowToMonitor { .. } => "move-window-to-monitor",
+            ActionType::OpenUrl { .. } => "url",
+            ActionType::Settings { .. } => "settings",
+            ActionType::Copilot => "copilot",
+            ActionType::ToggleTaskbarAutohide => "toggle-taskbar-autohide",
+            ActionType::Brightness { .. } => "brightness",
+            ActionType::ScrollWheel { .. } => "scroll",
+            ActionType::DisplayMode { .. } => "display-mode",
+            ActionType::ToggleHdr => "toggle-hdr",
+            ActionType::RotateDisplay { .. } => "rotate-display",
+            ActionType::SetResolution { .. } => "set-resolution",
+            ActionType::Script { .. } => "rhai",
+            ActionType::ToggleDesktopIcons => "toggle-desktop-icons",
+            ActionType::RunDialog => "run-dialog",
+            ActionType::Search => "search",
+            ActionType::GameBar => "game-bar",
+            ActionType::NightLight { .. } => "night-light",
+            ActionType::FocusAssist => "focus-assist",
+            ActionType::OnScreenKeyboard => "on-screen-keyboard",
+            ActionType::MobilityCenter => "mobility-center",
+            ActionType::CloseWindow { .. } => "close-window",
+            ActionType::WindowState { .. } => "window-state",
+            ActionType::Keys { .. } => "keys",
+            ActionType::Plugin { .. } => "plugin",
+        }
+    }
+}
+
+impl Config {
+    /// Every configured corner and zone, of every profile
+    pub fn all_corners(&self) -> impl Iterator<Item = &CornerConfig> {
+        self.corners.iter().chain(&self.zones).chain(
+            self.profiles
+                .iter()
+                .flat_map(|profile| profile.corners.iter().chain(&profile.zones)),
+        )
+    }
+
+    /// The corners and zones of the profile with index `profile`, or `corners` and `zones` if
+    /// there are no profiles
+    pub fn corners(&self, profile: usize) -> impl Iterator<Item = &CornerConfig> {
+        let (corners, zones) = self
+            .profiles
+            .get(profile)
+            .map_or((&self.corners, &self.zones), |profile| {
+                (&profile.corners, &profile.zones)
+            });
+        corners.iter().chain(zones)
+    }
+
+    /// Returns the configuration of `corner` in the profile with index `profile`, if it is enabled
+    pub fn corner(&self, profile: usize, corner: Corner) -> Option<&CornerConfig> {
+        self.corners(profile)
+            .find(|c| c.position == corner && c.enabled)
+    }
+
+    /// Index of the profile named `name`
+    pub fn profile_index(&self, name: &str) -> Option<usize> {
+        self.profiles
+            .iter()
+            .position(|profile| profile.name == name)
+    }
+
+    /// Index of the profile that is active at startup
+    pub fn default_profile(&self) -> usize {
+        self.default_profile
+            .as_deref()
+            .and_then(|name| self.profile_index(name))
+            .unwrap_or(0)
+    }
+
+    /// Name of the profile with index `profile`, if there is one
+    pub fn profile_name(&self, profile: usize) -> Option<&str> {
+        self.profiles
+            .get(profile)
+            .map(|profile| profile.name.as_str())
+    }
+
+    /// Size of `corner` in pixels, before validation against the monitor
+    pub fn corner_size(&self, corner: &CornerConfig) -> u32 {
+        corner
+            .size
+            .or(self.corner_size)
+            .unwrap_or(DEFAULT_CORNER_SIZE)
+    }
+
+    /// How to retry input `SendInput` didn't send, `None` without `retry_on_failure`
+    pub fn send_input_retry(&self) -> Option<RetryPolicy> {
+        self.retry_on_failure.then(|| RetryPolicy {
+            max_retries: self.max_retries,
+            delay: Duration::from_millis(self.retry_delay_ms),
+        })
+    }
+}
+
+/// How often and how fast to retry input `SendInput` didn't send
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Retries before giving up
+    pub max_retries: u32,
+    /// Wait before each retry
+    pub delay: Duration,
+}
+
+/// Only the top-left corner is active unless configured otherwise
+fn default_corners() -> Vec<CornerConfig> {
+    vec![CornerConfig {
+        position: Corner::TopLeft,
+        enabled: default_enabled(),
+        size: None,
+        shape: ZoneShape::default(),
+        delay_ms: None,
+        max_entry_velocity: None,
+        entry_directions: Vec::new(),
+        cooldown_ms: default_cooldown_ms(),
+        action: default_action(),
+    }]
+}
+
+/// Corners are enabled unless disabled
+const fn default_enabled() -> bool {
+    true
+}
+
+/// Fullscreen suppression is on unless disabled
+const fn default_suppress_fullscreen() -> bool {
+    true
+}
+
+/// Remote Desktop suppression is on unless disabled
+const fn default_suppress_in_remote_desktop() -> bool {
+    true
+}
+
+/// Keeps the log file small enough to open in any editor
+const fn default_max_log_size_mb() -> u64 {
+    10
+}
+
+/// Enough to get past a program that briefly holds the foreground lock
+const fn default_max_retries() -> u32 {
+    3
+}
+
+/// Retries 3 times within 150ms, before the user notices the delay much
+const fn default_retry_delay_ms() -> u64 {
+    50
+}
+
+/// Long enough to not fire twice when briefly leaving and re-entering a corner
+const fn default_cooldown_ms() -> u64 {
+    500
+}
+
+/// Volume actions change the volume by 5% unless configured otherwise
+const fn default_volume_step() -> f32 {
+    0.05
+}
+
+/// Opens Task View with `Win+Tab`
+fn default_action() -> ActionType {
+    ActionType::Keystroke(vec![
+        KeyEvent {
+            vk: VK_LWIN.0,
+            flags: 0,
+        },
+        KeyEvent {
+            vk: VK_TAB.0,
+            flags: 0,
+        },
+    ])
+}
+
+/// Accepts either a list of keys or a tagged action table for `CornerConfig::action`
+fn deserialize_action<'de, D: Deserializer<'de>>(deserializer: D) -> Result<ActionType, D::Error> {
+    struct ActionVisitor;
+
+    impl<'de> Visitor<'de> for ActionVisitor {
+        type Value = ActionType;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("a list of keys, an action type or an action table")
+        }
+
+        fn visit_str<E: serde::de::Error>(self, v: &str) -> Result<Self::Value, E> {
+            ActionType::deserialize(MapDeserializer::new(std::iter::once(("type", v))))
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(self, seq: A) -> Result<Self::Value, A::Error> {
+            Vec::deserialize(SeqAccessDeserializer::new(seq)).map(ActionType::Keystroke)
+        }
+
+        fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<Self::Value, A::Error> {
+            ActionType::deserialize(MapAccessDeserializer::new(map))
+        }
+    }
+
+    deserializer.deserialize_any(ActionVisitor)
+}
+
+/// Writes an action in the same shapes `deserialize_action` reads, a list of keys for
+/// `Keystroke` and a table with a `type` for everything else
+fn serialize_action<S: Serializer>(action: &ActionType, serializer: S) -> Result<S::Ok, S::Error> {
+    match action {
+        ActionType::Keystroke(keys) => keys.serialize(serializer),
+        action => action.serialize(serializer),
+    }
+}
+
+/// Checks every value of the configuration that doesn't depend on the monitor, collecting all
+/// problems instead of stopping at the first one
+pub fn validate_config(config: &Config) -> Result<(), Vec<ConfigError>> {
+    let mut errors = Vec::new();
+
+    if config.corner_size == Some(0) {
+        errors.push(ConfigError::invalid(
+            "corner_size",
+            0,
+            "a size greater than zero",
+        ));
+    }
+    validate_zones(config, "", &config.corners, &config.zones, &mut errors);
+    for (i, profile) in config.profiles.iter().enumerate() {
+        validate_zones(
+            config,
+            &format!("profiles[{i}]."),
+            &profile.corners,
+            &profile.zones,
+            &mut errors,
+        );
+    }
+
+    if let Some(name) = &config.default_profile {
+        if config.profile_index(name).is_none() {
+            errors.push(ConfigError::invalid(
+                "default_profile",
+                name,
+                "the name of one of the [[profiles]]",
+            ));
+        }
+    }
+
+    for (i, field) in config.locked.iter().enumerate() {
+        if !FIELDS.contains(&field.as_str()) {
+            errors.push(ConfigError::invalid(
+                format!("locked.fields[{i}]"),
+                field,
+                "the name of a top-level field",
+            ));
+        }
+    }
+
+    let profile_hotkey = validate_hotkey(
+        "profile_cycle_hotkey",
+        config.profile_cycle_hotkey.as_ref(),
+        &mut errors,
+    );
+    let exit_hotkey = validate_hotkey("exit_hotkey", config.exit_hotkey.as_ref(), &mut errors);
+    if profile_hotkey.is_some() && profile_hotkey == exit_hotkey {
+        errors.push(ConfigError::ConflictingFields {
+            field_a: "profile_cycle_hotkey".to_owned(),
+            field_b: "exit_hotkey".to_owned(),
+        });
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+/// Checks the `[[corners]]` and `[[zones]]` of `config` or of one of its profiles, `prefix` is
+/// prepended to the field names, e.g. `profiles[0].`
+fn validate_zones(
+    config: &Config,
+    prefix: &str,
+    corners: &[CornerConfig],
+    zones: &[CornerConfig],
+    errors: &mut Vec<ConfigError>,
+) {
+    validate_corners(config, &format!("{prefix}corners"), corners, errors);
+    validate_corners(config, &format!("{prefix}zones"), zones, errors);
+
+    // Like within each list, only one enabled entry can use a position
+    for (i, zone) in zones.iter().enumerate().filter(|(_, zone)| zone.enabled) {
+        let duplicate = corners
+            .iter()
+            .position(|corner| corner.enabled && corner.position == zone.position);
+        if let Some(j) = duplicate {
+            errors.push(ConfigError::ConflictingFields {
+                field_a: format!("{prefix}corners[{j}].position"),
+                field_b: format!("{prefix}zones[{i}].position"),
+            });
+        }
+    }
+}
+
+/// Checks a list of `[[corners]]` or `[[zones]]` entries of `config`, `field` is the name of the
+/// list
+fn validate_corners(
+    config: &Config,
+    field: &str,
+    corners: &[CornerConfig],
+    errors: &mut Vec<ConfigError>,
+) {
+    for (i, corner) in corners.iter().enumerate() {
+        let entry = format!("{field}[{i}]");
+        validate_corner(config, &entry, corner, errors);
+
+        // Only the first enabled entry for a position is used
+        let duplicate = corners[..i]
+            .iter()
+            .position(|other| other.enabled && other.position == corner.position);
+        if let Some(j) = duplicate.filter(|_| corner.enabled) {
+            errors.push(ConfigError::ConflictingFields {
+                field_a: format!("{field}[{j}].position"),
+                field_b: format!("{entry}.position"),
+            });
+        }
+    }
+}
+
+/// Checks the values of a `[[corners]]` entry that don't depend on the monitor
+fn validate_corner(
+    config: &Config,
+    entry: &str,
+    corner: &CornerConfig,
+    errors: &mut Vec<ConfigError>,
+) {
+    if corner.size == Some(0) {
+        errors.push(ConfigError::invalid(
+            format!("{entry}.size"),
+            0,
+            "a size greater than zero",
+        ));
+    }
+    if let Some(delay_ms) = corner.delay_ms.filter(|&delay_ms| delay_ms > MAX_DELAY_MS) {
+        errors.push(ConfigError::invalid(
+            format!("{entry}.delay_ms"),
+            delay_ms,
+            format_args!("at most {MAX_DELAY_MS} milliseconds"),
+        ));
+    }
+    if let Some(velocity) = corner.max_entry_velocity {
+        if velocity.is_nan() || velocity <= 0.0 {
+            errors.push(ConfigError::invalid(
+                format!("{entry}.max_entry_velocity"),
+                velocity,
+                "a speed greater than zero",
+            ));
+        }
+    }
+
+    validate_action(config, &format!("{entry}.action"), &corner.action, errors);
+}
+
+/// Checks that a configured action can be performed
+fn validate_action(
+    config: &Config,
+    field: &str,
+    action: &ActionType,
+    errors: &mut Vec<ConfigError>,
+) {
+    match action {
+        ActionType::Keystroke(keys) => validate_keys(field, keys, errors),
+        ActionType::Keys { sequence } => {
+            validate_sequence(&format!("{field}.sequence"), sequence, errors);
+        }
+        ActionType::VolumeUp { amount } | ActionType::VolumeDown { amount }
+            if amount.is_nan() || *amount < 0.0 =>
+        {
+            errors.push(ConfigError::invalid(
+                format!("{field}.amount"),
+                amount,
+                "a positive number",
+            ));
+        }
+        ActionType::ToggleWindow {
+            class: None,
+            title_contains: None,
+            ..
+        } => {
+            errors.push(ConfigError::invalid(
+                field,
+                "toggle-window",
+                "a class or title_contains to find the window by",
+            ));
+        }
+        ActionType::OpenUrl { url } => {
+            let allowed = url_scheme(url).is_some_and(|scheme| {
+                DEFAULT_URL_SCHEMES.contains(&scheme.as_str())
+                    || config
+                        .allowed_schemes
+                        .iter()
+                        .any(|allowed| allowed.eq_ignore_ascii_case(&scheme))
+            });
+            if !allowed {
+                errors.push(ConfigError::invalid(
+                    format!("{field}.url"),
+                    url,
+                    "an http or https URL, or one with a scheme from allowed_schemes",
+                ));
+            }
+        }
+        ActionType::Plugin { name } if name.is_empty() => {
+            errors.push(ConfigError::invalid(
+                format!("{field}.name"),
+                "\"\"",
+                "the name of a plugin's action type",
+            ));
+        }
+        ActionType::ScrollWheel { delta: 0 } => {
+            errors.push(ConfigError::invalid(
+                format!("{field}.delta"),
+                0,
+                "a number of notches other than zero",
+            ));
+        }
+        ActionType::RotateDisplay { degrees, .. } if !ROTATIONS.contains(degrees) => {
+            errors.push(ConfigError::invalid(
+                format!("{field}.degrees"),
+                degrees,
+                "0, 90, 180 or 270",
+            ));
+        }
+        ActionType::Script { script, path } => {
+            validate_script(field, script.as_deref(), path.as_deref(), errors);
+        }
+        ActionType::TypeText { text } if text.is_empty() => {
+            errors.push(ConfigError::invalid(
+                format!("{field}.text"),
+                "\"\"",
+                "at least one character",
+            ));
+        }
+        _ => {}
+    }
+}
+
+/// Checks that a `rhai` action has either a script or a path, and with the `scripting` feature
+/// that the script parses
+fn validate_script(
+    field: &str,
+    script: Option<&str>,
+    path: Option<&Path>,
+    errors: &mut Vec<ConfigError>,
+) {
+    match (script, path) {
+        (Some(_), Some(_)) => errors.push(ConfigError::ConflictingFields {
+            field_a: format!("{field}.script"),
+            field_b: format!("{field}.path"),
+        }),
+        (None, None) => errors.push(ConfigError::invalid(
+            field,
+            "rhai",
+            "a script or the path of one",
+        )),
+        #[cfg(feature = "scripting")]
+        (Some(script), None) => {
+            if let Err(e) = scripting::compile(script) {
+                errors.push(ConfigError::invalid(
+                    format!("{field}.script"),
+                    format!("invalid ({e})"),
+                    "a Rhai script",
+                ));
+            }
+        }
+        #[cfg(feature = "scripting")]
+        (None, Some(path)) => {
+            if let Err(e) = scripting::compile_file(path) {
+                errors.push(ConfigError::invalid(
+                    format!("{field}.path"),
+                    format!("{} ({e})", path.display()),
+                    "a readable Rhai script",
+                ));
+            }
+        }
+        // Without the feature, the action is only warned about
+        #[cfg(not(feature = "scripting"))]
+        _ => {}
+    }
+}
+
+/// Checks that a configured key combination can be sent
+fn validate_keys(field: &str, keys: &[KeyEvent], errors: &mut Vec<ConfigError>) {
+    if keys.is_empty() {
+        errors.push(ConfigError::invalid(field, "[]", "at least one key"));
+    }
+
+    for (i, key) in keys.iter().enumerate() {
+        if !is_known_vk(key.vk) {
+            errors.push(ConfigError::invalid(
+                format!("{field}[{i}].vk"),
+                format_args!("{:#04x}", key.vk),
+                "an assigned virtual key code",
+            ));
+        }
+        if key.flags & KEYEVENTF_KEYUP.0 != 0 {
+            errors.push(ConfigError::invalid(
+                format!("{field}[{i}].flags"),
+                key.flags,
+                "flags without KEYEVENTF_KEYUP, key up events are sent automatically",
+            ));
+        }
+    }
+}
+
+/// Checks the events of a `keys` action, including that every key pressed is released again
+fn validate_sequence(field: &str, sequence: &[KeySequenceEvent], errors: &mut Vec<ConfigError>) {
+    if sequence.is_empty() {
+        errors.push(ConfigError::invalid(field, "[]", "at least one key event"));
+    }
+
+    let mut held = Vec::new();
+    for (i, event) in sequence.iter().enumerate() {
+        let Some(vk) = event.vk.resolve().filter(|vk| is_known_vk(vk.0)) else {
+            errors.push(ConfigError::invalid(
+                format!("{field}[{i}].vk"),
+                match &event.vk {
+                    KeyCode::Code(vk) => format!("{vk:#04x}"),
+                    KeyCode::Name(name) => format!("\"{name}\""),
+                },
+                "an assigned virtual key code or the name of one, e.g. \"VK_TAB\"",
+            ));
+            continue;
+        };
+        if event.flags & KEYEVENTF_KEYUP.0 != 0 {
+            errors.push(ConfigError::invalid(
+                format!("{field}[{i}].flags"),
+                event.flags,
+                "flags without KEYEVENTF_KEYUP, use down = false",
+            ));
+        }
+        if event.down {
+            held.push(vk);
+        } else if let Some(pos) = held.iter().position(|&key| key == vk) {
+            held.remove(pos);
+        }
+    }
+
+    if let Some(vk) = held.first() {
+        errors.push(ConfigError::invalid(
+            field,
+            format_args!("a sequence that leaves {:#04x} pressed", vk.0),
+            "every key pressed to be released again",
+        ));
+    }
+}
+
+/// Checks a configured hotkey, returning the parsed hotkey if it is valid
+fn validate_hotkey(
+    field: &str,
+    hotkey: Option<&HotkeyConfig>,
+    errors: &mut Vec<ConfigError>,
+) -> Option<(VIRTUAL_KEY, HOT_KEY_MODIFIERS)> {
+    let hotkey = hotkey?;
+    match parse_hotkey(hotkey) {
+        Ok(parsed) => Some(parsed),
+        Err(e) => {
+            let keys: Vec<&str> = hotkey
+                .modifiers
+                .iter()
+                .chain(Some(&hotkey.key))
+                .map(String::as_str)
+                .collect();
+            errors.push(ConfigError::invalid(
+                field,
+                keys.join("+"),
+                format_args!("a valid hotkey, got {e}"),
+            ));
+            None
+        }
+    }
+}
+
+/// Whether `vk` is an assigned virtual key code, see
+/// <https://learn.microsoft.com/en-us/windows/win32/inputdev/virtual-key-codes>
+fn is_known_vk(vk: u16) -> bool {
+    !matches!(
+        vk,
+        0x00 | 0x07
+            | 0x0A..=0x0B
+            | 0x0E..=0x0F
+            | 0x3A..=0x40
+            | 0x5E
+            | 0x88..=0x8F
+            | 0x97..=0x9F
+            | 0xB8..=0xB9
+            | 0xC1..=0xDA
+            | 0xE0
+            | 0xE8
+            | 0xFF..
+    )
+}
+
+/// Converts a configured hotkey to the key and modifiers `RegisterHotKey` expects
+pub fn parse_hotkey(config: &HotkeyConfig) -> Result<(VIRTUAL_KEY, HOT_KEY_MODIFIERS), String> {
+    let key = parse_key(&config.key).ok_or_else(|| format!("unknown key {}", config.key))?;
+
+    let mut modifiers = HOT_KEY_MODIFIERS(0);
+    for modifier in &config.modifiers {
+        modifiers |= match modifier.to_ascii_lowercase().as_str() {
+            "ctrl" | "control" => MOD_CONTROL,
+            "alt" => MOD_ALT,
+            "shift" => MOD_SHIFT,
+            "win" => MOD_WIN,
+            _ => {
+                return Err(format!(
+                    "unknown modifier {modifier}, expected ctrl, alt, shift or win"
+                ))
+            }
+        };
+    }
+
+    Ok((key, modifiers))
+}
+
+/// Looks up the virtual key for a key name of a `keys` action, which can also be a modifier and
+/// start with `VK_`, ignoring case
+fn parse_vk_name(name: &str) -> Option<VIRTUAL_KEY> {
+    let upper = name.to_ascii_uppercase();
+    let name = upper.strip_prefix("VK_").unwrap_or(&upper);
+    Some(match name {
+        "LWIN" | "WIN" => VK_LWIN,
+        "RWIN" => VK_RWIN,
+        "CONTROL" | "CTRL" => VK_CONTROL,
+        "LCONTROL" => VK_LCONTROL,
+        "RCONTROL" => VK_RCONTROL,
+        "SHIFT" => VK_SHIFT,
+        "LSHIFT" => VK_LSHIFT,
+        "RSHIFT" => VK_RSHIFT,
+        "MENU" | "ALT" => VK_MENU,
+        "LMENU" => VK_LMENU,
+        "RMENU" => VK_RMENU,
+        "RETURN" => VK_RETURN,
+        "BACK" => VK_BACK,
+        "PRIOR" => VK_PRIOR,
+        "NEXT" => VK_NEXT,
+        _ => return parse_key(name),
+    })
+}
+
+/// Looks up the virtual key for a key name, ignoring case
+fn parse_key(name: &str) -> Option<VIRTUAL_KEY> {
+    let name = name.to_ascii_uppercase();
+
+    // Letters and digits use their ASCII code
+    if let [c @ (b'A'..=b'Z' | b'0'..=b'9')] = name.as_bytes() {
+        return Some(VIRTUAL_KEY(u16::from(*c)));
+    }
+    if let Some(n) = name.strip_prefix('F').and_then(|n| n.parse::<u16>().ok()) {
+        return (1..=24).contains(&n).then(|| VIRTUAL_KEY(VK_F1.0 + n - 1));
+    }
+
+    Some(match name.as_str() {
+        "SPACE" => VK_SPACE,
+        "TAB" => VK_TAB,
+        "ENTER" => VK_RETURN,
+        "ESCAPE" | "ESC" => VK_ESCAPE,
+        "BACKSPACE" => VK_BACK,
+        "INSERT" => VK_INSERT,
+        "DELETE" => VK_DELETE,
+        "HOME" => VK_HOME,
+        "END" => VK_END,
+        "PAGEUP" => VK_PRIOR,
+        "PAGEDOWN" => VK_NEXT,
+        "LEFT" => VK_LEFT,
+        "RIGHT" => VK_RIGHT,
+        "UP" => VK_UP,
+        "DOWN" => VK_DOWN,
+        _ => return None,
+    })
+}
+
+/// Checks a configured corner size against the monitor it will be used on.
+///
+/// Returns `None` for a size of zero. Sizes larger than half of the monitor's smaller dimension
+/// would make corners overlap, so they are clamped to that limit with a warning. The returned size
+/// is still unscaled.
+pub fn validate_corner_size(size: u32, monitor: &MonitorInfo) -> Option<u32> {
+    if size == 0 {
+        return None;
+    }
+
+    let bounds = monitor.bounds;
+    let smallest = (bounds.right - bounds.left).min(bounds.bottom - bounds.top);
+    let half = u32::try_from(smallest / 2).unwrap_or(0);
+    let limit = (u64::from(half) * u64::from(DEFAULT_DPI) / u64::from(monitor.dpi.max(1))).max(1);
+    let limit = u32::try_from(limit).unwrap_or(u32::MAX);
+    if size > limit {
+        warn!("Corner size {size} exceeds half of the screen, clamping to {limit}");
+        return Some(limit);
+    }
+
+    Some(size)
+}
+
+/// Errors that can occur while loading the configuration file
+#[derive(Debug)]
+pub enum LoadError {
+    /// The file exists but couldn't be read
+    Io(PathBuf, io::Error),
+    /// The file isn't valid TOML or doesn't match the expected layout
+    Parse(PathBuf, Box<toml::de::Error>),
+    /// The file parsed but contains values that can't be used
+    Invalid(PathBuf, Vec<ConfigError>),
+}
+
+/// A value in the configuration file that can't be used
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigError {
+    /// A field has a value that can't be used
+    InvalidField {
+        /// Path of the field, e.g. `corners[0].size`
+        field: String,
+        /// The configured value
+        value: String,
+        /// What the value should look like
+        expected: String,
+    },
+    /// Two fields can't be used together
+    ConflictingFields {
+        /// Path of the first field
+        field_a: String,
+        /// Path of the second field
+        field_b: String,
+    },
+}
+
+impl ConfigError {
+    /// An `InvalidField` error
+    fn invalid(
+        field: impl Into<String>,
+        value: impl fmt::Display,
+        expected: impl fmt::Display,
+    ) -> Self {
+        Self::InvalidField {
+            field: field.into(),
+            value: value.to_string(),
+            expected: expected.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::InvalidField {
+                field,
+                value,
+                expected,
+            } => write!(f, "{field} is {value}, expected {expected}"),
+            ConfigError::ConflictingFields { field_a, field_b } => {
+                write!(f, "{field_a} conflicts with {field_b}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadError::Io(path, e) => write!(f, "failed to read {}: {e}", path.display()),
+            LoadError::Parse(path, e) => write!(f, "failed to parse {}: {e}", path.display()),
+            LoadError::Invalid(path, errors) => {
+                write!(f, "invalid config {}:", path.display())?;
+                for error in errors {
+                    write!(f, "\n  {error}")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for LoadError {}
+
+/// Location of the configuration file. In order of preference:
+///
+/// 1. The file set with `set_config_path`, from `--config`
+/// 2. `config.toml` next to the executable, for portable installs
+/// 3. `%APPDATA%\hotcorners\config.toml`
+pub fn resolve_config_path() -> Option<PathBuf> {
+    choose_config_path(
+        CONFIG_PATH.get().map(PathBuf::as_path),
+        portable_config_path(),
+        env::var_os("APPDATA").map(PathBuf::from).as_deref(),
+        Path::is_file,
+    )
+}
+
+/// `resolve_config_path` for the `--config` path `explicit`, the portable path `portable` and the
+/// `%APPDATA%` directory `appdata`, with `is_file` telling whether a file exists
+fn choose_config_path(
+    explicit: Option<&Path>,
+    portable: Option<PathBuf>,
+    appdata: Option<&Path>,
+    is_file: impl Fn(&Path) -> bool,
+) -> Option<PathBuf> {
+    if let Some(path) = explicit {
+        return Some(path.to_owned());
+    }
+    if let Some(path) = portable.filter(|path| is_file(path)) {
+        return Some(path);
+    }
+    appdata.map(|appdata| appdata.join(CONFIG_DIR).join(CONFIG_FILE))
+}
+
+/// `config.toml` in the directory of the executable
+fn portable_config_path() -> Option<PathBuf> {
+    let exe = env::current_exe().ok()?;
+    Some(exe.parent()?.join(CONFIG_FILE))
+}
+
+/// Reads the configuration from `path` instead of the default location. Relative paths are
+/// resolved against the current directory.
+pub fn set_config_path(path: &Path) -> Result<(), String> {
+    let path = std::path::absolute(path).map_err(|e| format!("Invalid config path: {e}"))?;
+    CONFIG_PATH
+        .set(path)
+        .map_err(|_| "The config path can only be set once".to_owned())
+}
+
+/// Writes a commented default configuration file to `resolve_config_path`, creating its
+/// directory. An existing file is only replaced if `force` is set.
+///
+/// Returns the path of the written file.
+pub fn init(force: bool) -> Result<PathBuf, String> {
+    let path = resolve_config_path().ok_or("%APPDATA% is not set")?;
+    if path.exists() && !force {
+        return Err(format!(
+            "{} already exists, use --init --force to replace it",
+            path.display()
+        ));
+    }
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {e}", dir.display()))?;
+    }
+
+    // Written next to the real file first, so an interrupted write can't leave a broken config
+    let temp = path.with_extension("toml.tmp");
+    fs::write(&temp, DEFAULT_CONFIG)
+        .and_then(|()| fs::rename(&temp, &path))
+        .map_err(|e| format!("Failed to write {}: {e}", path.display()))?;
+
+    Ok(path)
+}
+
+/// Location of the system-wide configuration file, `%ProgramData%\hotcorners\config.toml`
+pub fn system_config_path() -> Option<PathBuf> {
+    env::var_os("ProgramData").map(|data| PathBuf::from(data).join(CONFIG_DIR).join(CONFIG_FILE))
+}
+
+/// Applies every field set in `overlay` over `base`, except for the fields `base` locked. Locks
+/// set in `overlay` are added to those of `base`.
+// One call per field, splitting it up wouldn't make it any easier to follow
+#[allow(clippy::too_many_lines)]
+pub fn merge_configs(base: Config, overlay: PartialConfig) -> Config {
+    let mut config = base;
+    let locked = config.locked.clone();
+    let locked = locked.as_slice();
+
+    overlay_field(&mut config.corners, overlay.corners, "corners", locked);
+    overlay_field(&mut config.zones, overlay.zones, "zones", locked);
+    overlay_field(
+        &mut config.corner_size,
+        overlay.corner_size.map(Some),
+        "corner_size",
+        locked,
+    );
+    overlay_field(
+        &mut config.suppress_fullscreen,
+        overlay.suppress_fullscreen,
+        "suppress_fullscreen",
+        locked,
+    );
+    overlay_field(
+        &mut config.suppress_for,
+        overlay.suppress_for,
+        "suppress_for",
+        locked,
+    );
+    overlay_field(
+        &mut config.suppress_for_class,
+        overlay.suppress_for_class,
+        "suppress_for_class",
+        locked,
+    );
+    overlay_field(
+        &mut config.suppress_touch,
+        overlay.suppress_touch,
+        "suppress_touch",
+        locked,
+    );
+    overlay_field(
+        &mut config.disable_on_battery,
+        overlay.disable_on_battery,
+        "disable_on_battery",
+        locked,
+    );
+    overlay_field(
+        &mut config.suppress_in_remote_desktop,
+        overlay.suppress_in_remote_desktop,
+        "suppress_in_remote_desktop",
+        locked,
+    );
+    overlay_field(&mut config.profiles, overlay.profiles, "profiles", locked);
+    overlay_field(
+        &mut config.default_profile,
+        overlay.default_profile.map(Some),
+        "default_profile",
+        locked,
+    );
+    overlay_field(
+        &mut config.profile_cycle_hotkey,
+        overlay.profile_cycle_hotkey.map(Some),
+        "profile_cycle_hotkey",
+        locked,
+    );
+    overlay_field(
+        &mut config.exit_hotkey,
+        overlay.exit_hotkey.map(Some),
+        "exit_hotkey",
+        locked,
+    );
+    overlay_field(
+        &mut config.auto_fallback_hotkey,
+        overlay.auto_fallback_hotkey,
+        "auto_fallback_hotkey",
+        locked,
+    );
+    overlay_field(
+        &mut config.log_file,
+        overlay.log_file.map(Some),
+        "log_file",
+        locked,
+    );
+    overlay_field(
+        &mut config.max_log_size_mb,
+        overlay.max_log_size_mb,
+        "max_log_size_mb",
+        locked,
+    );
+    overlay_field(
+        &mut config.allowed_schemes,
+        overlay.allowed_schemes,
+        "allowed_schemes",
+        locked,
+    );
+    overlay_field(
+        &mut config.plugin_paths,
+        overlay.plugin_paths,
+        "plugin_paths",
+        locked,
+    );
+    overlay_field(
+        &mut config.notify_on_action_error,
+        overlay.notify_on_action_error,
+        "notify_on_action_error",
+        locked,
+    );
+    overlay_field(
+        &mut config.retry_on_failure,
+        overlay.retry_on_failure,
+        "retry_on_failure",
+        locked,
+    );
+    overlay_field(
+        &mut config.max_retries,
+        overlay.max_retries,
+        "max_retries",
+        locked,
+    );
+    overlay_field(
+        &mut config.retry_delay_ms,
+        overlay.retry_delay_ms,
+        "retry_delay_ms",
+        locked,
+    );
+
+    if let Some(fields) = overlay.locked {
+        config.locked.extend(fields.fields);
+    }
+    config
+}
+
+/// Replaces `target` with `value` if it is set, unless `field` is in `locked`
+fn overlay_field<T>(target: &mut T, value: Option<T>, field: &str, locked: &[String]) {
+    let Some(value) = value else {
+        return;
+    };
+    if locked.iter().any(|locked| locked == field) {
+        warn!("{field} is locked by the system-wide configuration, ignoring it");
+        return;
+    }
+    *target = value;
+}
+
+/// Reads and parses one configuration file
+fn read_partial(path: &Path) -> Result<PartialConfig, LoadError> {
+    let contents = fs::read_to_string(path).map_err(|e| LoadError::Io(path.to_path_buf(), e))?;
+    toml::from_str(&contents).map_err(|e| LoadError::Parse(path.to_path_buf(), Box::new(e)))
+}
+
+/// Loads the system-wide configuration file and the user's configuration file over it, falling
+/// back to the defaults for everything neither of them sets
+pub fn load() -> Result<Config, LoadError> {
+    let mut config = Config::default();
+    let mut loaded = None;
+
+    if let Some(path) = system_config_path().filter(|path| path.exists()) {
+        config = merge_configs(config, read_partial(&path)?);
+        loaded = Some(path);
+    }
+
+    if let Some(path) = resolve_config_path().filter(|path| path.exists()) {
+        let partial = read_partial(&path)?;
+        if partial.locked.is_some() {
+            let error = ConfigError::invalid(
+                "locked",
+                "set",
+                "no [locked] section outside the system-wide configuration",
+            );
+            return Err(LoadError::Invalid(path, vec![error]));
+        }
+        config = merge_configs(config, partial);
+        loaded = Some(path);
+    }
+
+    if let Some(path) = loaded {
+        validate_config(&config).map_err(|errors| LoadError::Invalid(path, errors))?;
+    }
+    warn_unsupported_actions(&config);
+
+    Ok(config)
+}
+
+/// Warns about actions this build can't perform, they do nothing when their corner activates, and
+/// about actions that probably won't work
+fn warn_unsupported_actions(config: &Config) {
+    for corner in config.all_corners() {
+        if let Some(feature) = corner.action.missing_feature() {
+            warn!(
+                "The {} action of the {} corner does nothing, hotcorners was built without the \
+                 {feature} feature",
+                corner.action.name(),
+                corner.position
+            );
+        }
+        match &corner.action {
+            ActionType::Settings { page } if !SETTINGS_PAGES.contains(&page.as_str()) => {
+                warn!(
+                    "Unknown Settings page {page} for the {} corner, it may not open",
+                    corner.position
+                );
+            }
+            ActionType::Copilot
+                if windows_build().is_some_and(|build| build < COPILOT_MIN_BUILD) =>
+            {
+                warn!("Copilot action requires Windows 11; configured but may not work");
+            }
+            ActionType::SetResolution {
+                width,
+                height,
+                monitor_name,
+                ..
+            } if !display::supports_resolution(*width, *height, monitor_name.as_deref()) => {
+                warn!(
+                    "The display of the set-resolution action of the {} corner doesn't support \
+                     {width}x{height}, it won't work",
+                    corner.position
+                );
+            }
+            ActionType::Search => match windows_build() {
+                Some(build) if build >= WINDOWS_11_BUILD => {
+                    info!(
+                        "The search action of the {} corner opens the Windows 11 search panel",
+                        corner.position
+                    );
+                }
+                Some(_) => {
+                    info!(
+                        "The search action of the {} corner opens the Windows 10 search box, or \
+                         Cortana",
+                        corner.position
+                    );
+                }
+                None => {}
+            },
+            ActionType::MobilityCenter if unsafe { GetSystemMetrics(SM_TABLETPC) } == 0 => {
+                warn!(
+                    "This doesn't look like a laptop or tablet, the mobility-center action of the \
+                     {} corner may not work",
+                    corner.position
+                );
+            }
+            ActionType::GameBar if !is_game_bar_enabled() => {
+                warn!(
+                    "The Game Bar is turned off in the settings, the game-bar action of the {} \
+                     corner won't open it",
+                    corner.position
+                );
+            }
+            ActionType::ToggleHdr if !display::is_hdr_supported() => {
+                warn!(
+                    "The primary display doesn't support HDR, the toggle-hdr action of the {} \
+                     corner won't work",
+                    corner.position
+                );
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::*;
+    use crate::input;
+
+    /// Parses `toml` as a whole configuration file
+    fn parse(toml: &str) -> Config {
+        toml::from_str(toml).expect("the configuration parses")
+    }
+
+    /// Portable configuration of an executable in `C:\hotcorners`
+    const PORTABLE: &str = r"C:\hotcorners\config.toml";
+    /// `%APPDATA%` of the test user
+    const APPDATA: &str = r"C:\Users\me\AppData\Roaming";
+
+    #[test]
+    fn config_argument_wins() {
+        let path = choose_config_path(
+            Some(Path::new(r"D:\other.toml")),
+            Some(PathBuf::from(PORTABLE)),
+            Some(Path::new(APPDATA)),
+            |_| true,
+        );
+        assert_eq!(path, Some(PathBuf::from(r"D:\other.toml")));
+    }
+
+    #[test]
+    fn portable_config_wins_over_appdata_if_it_exists() {
+        let path = choose_config_path(
+            None,
+            Some(PathBuf::from(PORTABLE)),
+            Some(Path::new(APPDATA)),
+            |path| path == Path::new(PORTABLE),
+        );
+        assert_eq!(path, Some(PathBuf::from(PORTABLE)));
+    }
+
+    #[test]
+    fn appdata_config_is_the_fallback() {
+        let expected = Some(PathBuf::from(
+            r"C:\Users\me\AppData\Roaming\hotcorners\config.toml",
+        ));
+        let missing_portable = choose_config_path(
+            None,
+            Some(PathBuf::from(PORTABLE)),
+            Some(Path::new(APPDATA)),
+            |_| false,
+        );
+        assert_eq!(missing_portable, expected);
+        let no_exe_dir = choose_config_path(None, None, Some(Path::new(APPDATA)), |_| true);
+        assert_eq!(no_exe_dir, expected);
+    }
+
+    #[test]
+    fn no_config_path_without_appdata() {
+        let path = choose_config_path(None, Some(PathBuf::from(PORTABLE)), None, |_| false);
+        assert_eq!(path, None);
+    }
+
+    #[test]
+    fn lock_screen_parses_from_its_type() {
+        let config = parse(
+            r#"
+            [[corners]]
+            position = "top-left"
+            action = "lock-screen"
+            "#,
+        );
+        assert_eq!(config.corners[0].action, ActionType::LockScreen);
+    }
+
+    #[test]
+    fn lock_screen_parses_from_a_table() {
+        let config = parse(
+            r#"
+            [[corners]]
+            position = "bottom-right"
+            action = { type = "lock-screen" }
+            "#,
+        );
+        assert_eq!(config.corners[0].action, ActionType::LockScreen);
+        assert!(validate_config(&config).is_ok());
+    }
+
+    #[test]
+    fn zones_are_active_alongside_corners() {
+        let config = parse(
+            r#"
+            [[corners]]
+            position = "top-left"
+
+            [[zones]]
+            position = "left-edge"
+            action = "show-desktop"
+            "#,
+        );
+        assert!(validate_config(&config).is_ok());
+        let zone = config
+            .corner(0, Corner::LeftEdge)
+            .expect("the zone is enabled");
+        assert_eq!(zone.action, ActionType::ShowDesktop);
+        assert!(config.corner(0, Corner::TopLeft).is_some());
+    }
+
+    #[test]
+    fn zones_conflict_with_corners_at_the_same_position() {
+        let config = parse(
+            r#"
+            [[corners]]
+            position = "left-edge"
+
+            [[zones]]
+            position = "left-edge"
+            "#,
+        );
+        assert_eq!(
+            validate_config(&config),
+            Err(vec![ConfigError::ConflictingFields {
+                field_a: "corners[0].position".to_owned(),
+                field_b: "zones[0].position".to_owned(),
+            }])
+        );
+    }
+
+    #[test]
+    fn delay_ms_past_the_maximum_is_rejected() {
+        let config = parse(
+            r#"
+            [[corners]]
+            position = "top-left"
+            delay_ms = 10000
+
+            [[zones]]
+            position = "top-edge"
+            delay_ms = 9223372036854775807
+            "#,
+        );
+        assert_eq!(
+            validate_config(&config),
+            Err(vec![ConfigError::InvalidField {
+                field: "zones[0].delay_ms".to_owned(),
+                value: i64::MAX.to_string(),
+                expected: "at most 10000 milliseconds".to_owned(),
+            }])
+        );
+    }
+
+    #[test]
+    fn lock_screen_calls_win32_instead_of_sending_input() {
+        let action = ActionType::LockScreen;
+        assert_eq!(action.name(), "lock-screen");
+        assert!(input::build_action_inputs(&action).is_empty());
+    }
+
+    /// Largest integer TOML can represent, `toml` can't write bigger `u64` values
+    const TOML_MAX: u64 = i64::MAX.unsigned_abs();
+
+    /// Values of any TOML type, including integers TOML can't represent
+    fn any_value() -> impl Strategy<Value = String> {
+        prop_oneof![
+            Just(u64::MAX.to_string()),
+            any::<u64>().prop_map(|v| v.to_string()),
+            any::<i64>().prop_map(|v| v.to_string()),
+            any::<f64>().prop_map(|v| format!("{v:?}")),
+            any::<bool>().prop_map(|v| v.to_string()),
+            "[a-z-]{0,20}".prop_map(|v| format!("{v:?}")),
+            Just("[]".to_owned()),
+            Just("{}".to_owned()),
+        ]
+    }
+
+    /// Action types that exist, some that don't and values that aren't strings
+    fn any_action_type() -> impl Strategy<Value = String> {
+        prop_oneof![
+            prop::sample::select(vec![
+                "exec",
+                "lock-screen",
+                "show-desktop",
+                "volume-up",
+                "cycle-audio-device",
+                "url",
+                "keys",
+                "script",
+            ])
+            .prop_map(|v| format!("{v:?}")),
+            any_value(),
+        ]
+    }
+
+    /// Actions `serialize_action` writes in each of its shapes
+    fn action() -> impl Strategy<Value = ActionType> {
+        prop_oneof![
+            prop::collection::vec(
+                (any::<u16>(), any::<u32>()).prop_map(|(vk, flags)| KeyEvent { vk, flags }),
+                0..4,
+            )
+            .prop_map(ActionType::Keystroke),
+            (
+                any::<String>(),
+                prop::collection::vec(any::<String>(), 0..3)
+            )
+                .prop_map(|(path, args)| ActionType::Exec {
+                    path: PathBuf::from(path),
+                    args,
+                }),
+            Just(ActionType::LockScreen),
+            Just(ActionType::ShowDesktop),
+            (0.0f32..=1.0).prop_map(|amount| ActionType::VolumeUp { amount }),
+        ]
+    }
+
+    /// Corner entries with every field set to a value TOML can represent
+    fn corner_config() -> impl Strategy<Value = CornerConfig> {
+        (
+            prop::sample::select(Corner::ALL.to_vec()),
+            any::<bool>(),
+            prop::option::of(any::<u32>()),
+            prop::sample::select(vec![ZoneShape::Rectangle, ZoneShape::Triangle]),
+            prop::option::of(0..=TOML_MAX),
+            prop::option::of(0.0..1000.0f64),
+            prop::collection::vec(
+                prop::sample::select(vec![
+                    Direction::FromLeft,
+                    Direction::FromRight,
+                    Direction::FromAbove,
+                    Direction::FromBelow,
+                ]),
+                0..4,
+            ),
+            0..=TOML_MAX,
+            action(),
+        )
+            .prop_map(
+                |(
+                    position,
+                    enabled,
+                    size,
+                    shape,
+                    delay_ms,
+                    max_entry_velocity,
+                    entry_directions,
+                    cooldown_ms,
+                    action,
+                )| CornerConfig {
+                    position,
+                    enabled,
+                    size,
+                    shape,
+                    delay_ms,
+                    max_entry_velocity,
+                    entry_directions,
+                    cooldown_ms,
+                    action,
+                },
+            )
+    }
+
+    /// Configurations with random corners and top-level values
+    fn config() -> impl Strategy<Value = Config> {
+        (
+            prop::collection::vec(corner_config(), 0..4),
+            prop::option::of(any::<u32>()),
+            any::<bool>(),
+            prop::collection::vec(any::<String>(), 0..3),
+            prop::option::of(any::<String>()),
+            0..=TOML_MAX,
+            any::<u32>(),
+            0..=TOML_MAX,
+        )
+            .prop_map(
+                |(
+                    corners,
+                    corner_size,
+                    suppress_fullscreen,
+                    suppress_for,
+                    log_file,
+                    max_log_size_mb,
+                    max_retries,
+                    retry_delay_ms,
+                )| Config {
+                    corners,
+                    corner_size,
+                    suppress_fullscreen,
+                    suppress_for,
+                    log_file: log_file.map(PathBuf::from),
+                    max_log_size_mb,
+                    max_retries,
+                    retry_delay_ms,
+                    ..Config::default()
+                },
+            )
+    }
+
+    proptest! {
+        #[test]
+        fn arbitrary_text_never_panics(text in any::<String>()) {
+            let _ = toml::from_str::<Config>(&text);
+        }
+
+        #[test]
+        fn arbitrary_values_never_panic(
+            corner_size in any_value(),
+            position in any_value(),
+            size in any_value(),
+            delay_ms in any_value(),
+            cooldown_ms in any_value(),
+            action_type in any_action_type(),
+        ) {
+            let toml = format!(
+                "corner_size = {corner_size}\n\
+                 [[corners]]\n\
+                 position = {position}\n\
+                 size = {size}\n\
+                 delay_ms = {delay_ms}\n\
+                 cooldown_ms = {cooldown_ms}\n\
+                 action = {{ type = {action_type} }}\n"
+            );
+            if let Ok(config) = toml::from_str::<Config>(&toml) {
+                let _ = validate_config(&config);
+            }
+        }
+
+        #[test]
+        fn action_types_never_panic(action_type in any_action_type()) {
+            let toml = format!("[[corners]]\nposition = \"top-left\"\naction = {action_type}\n");
+            let _ = toml::from_str::<Config>(&toml);
+        }
+
+        #[test]
+        fn config_round_trips_through_toml(config in config()) {
+            let toml = toml::to_string(&config).expect("the configuration serializes");
+            let parsed =
+                toml::from_str::<Config>(&toml).expect("the serialized configuration parses");
+            prop_assert_eq!(parsed, config);
+        }
+    }
+}
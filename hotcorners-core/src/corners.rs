@@ -0,0 +1,742 @@
+//! Screen corner geometry and per-corner activation state
+
+use std::{
+    fmt,
+    str::FromStr,
+    sync::{
+        atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering},
+        Mutex, OnceLock,
+    },
+    thread::{JoinHandle, Thread},
+};
+
+use serde::{Deserialize, Serialize};
+use windows::Win32::{
+    Foundation::{POINT, RECT},
+    Graphics::Gdi::PtInRect,
+    System::SystemInformation::GetTickCount64,
+};
+
+use crate::{
+    config::{self, Config, CornerConfig, RetryPolicy},
+    monitors::{enumerate_monitors, MonitorInfo},
+};
+
+/// Default size of the on-screen part of a hot corner, in pixels at `DEFAULT_DPI`
+pub const DEFAULT_CORNER_SIZE: u32 = 20;
+
+/// DPI of a monitor at 100% scaling, corner sizes are given for this DPI
+pub const DEFAULT_DPI: u32 = 96;
+
+/// How far a hot corner extends past the edges of the screen.
+///
+/// Fixes the activation issue when the mouse tries to go through the corner: the cursor can
+/// briefly be reported outside the screen bounds, so the corners are made larger than they look.
+const OVERSHOOT: i32 = 200;
+
+/// One of the four corners of a screen, or one of its four edges
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Corner {
+    /// Top-left corner
+    TopLeft,
+    /// Top-right corner
+    TopRight,
+    /// Bottom-left corner
+    BottomLeft,
+    /// Bottom-right corner
+    BottomRight,
+    /// The whole left edge
+    LeftEdge,
+    /// The whole right edge
+    RightEdge,
+    /// The whole top edge
+    TopEdge,
+    /// The whole bottom edge
+    BottomEdge,
+}
+
+impl Corner {
+    /// Every corner and edge
+    pub const ALL: [Corner; 8] = [
+        Corner::TopLeft,
+        Corner::TopRight,
+        Corner::BottomLeft,
+        Corner::BottomRight,
+        Corner::LeftEdge,
+        Corner::RightEdge,
+        Corner::TopEdge,
+        Corner::BottomEdge,
+    ];
+
+    /// Bit that represents this corner in a set of corners
+    pub const fn mask(self) -> u8 {
+        1 << self as u8
+    }
+
+    /// Whether this is a whole edge rather than a corner
+    pub const fn is_edge(self) -> bool {
+        matches!(
+            self,
+            Corner::LeftEdge | Corner::RightEdge | Corner::TopEdge | Corner::BottomEdge
+        )
+    }
+
+    /// Whether this is on the left side of the screen
+    pub const fn is_left(self) -> bool {
+        matches!(
+            self,
+            Corner::TopLeft | Corner::BottomLeft | Corner::LeftEdge
+        )
+    }
+
+    /// Whether this is on the right side of the screen
+    pub const fn is_right(self) -> bool {
+        matches!(
+            self,
+            Corner::TopRight | Corner::BottomRight | Corner::RightEdge
+        )
+    }
+
+    /// Whether this is on the top side of the screen
+    pub const fn is_top(self) -> bool {
+        matches!(self, Corner::TopLeft | Corner::TopRight | Corner::TopEdge)
+    }
+
+    /// Whether this is on the bottom side of the screen
+    pub const fn is_bottom(self) -> bool {
+        matches!(
+            self,
+            Corner::BottomLeft | Corner::BottomRight | Corner::BottomEdge
+        )
+    }
+}
+
+impl fmt::Display for Corner {
+    /// Writes the name used in `config.toml`, e.g. `top-left`
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Corner::TopLeft => "top-left",
+            Corner::TopRight => "top-right",
+            Corner::BottomLeft => "bottom-left",
+            Corner::BottomRight => "bottom-right",
+            Corner::LeftEdge => "left-edge",
+            Corner::RightEdge => "right-edge",
+            Corner::TopEdge => "top-edge",
+            Corner::BottomEdge => "bottom-edge",
+        })
+    }
+}
+
+impl FromStr for Corner {
+    type Err = String;
+
+    /// Parses the name used in `config.toml`, e.g. `top-left`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Corner::ALL
+            .into_iter()
+            .find(|corner| corner.to_string() == s)
+            .ok_or_else(|| {
+                format!(
+                    "Unknown corner {s}, expected top-left, top-right, bottom-left, bottom-right, \
+                     left-edge, right-edge, top-edge or bottom-edge"
+                )
+            })
+    }
+}
+
+/// The thread that performs a corner's action, and the flags the mouse hook controls it with.
+///
+/// Every corner has its own worker, so corners on different monitors can activate at the same
+/// time.
+#[derive(Debug, Default)]
+pub struct CornerWorker {
+    /// Set by the mouse hook to wake the worker thread, cleared by the worker once it is done
+    pub arm_flag: AtomicBool,
+    /// Set by the mouse hook when the cursor leaves the corner, so a worker that is still waiting
+    /// out the delay doesn't perform the action. Cleared whenever the worker is woken.
+    pub cancel_flag: AtomicBool,
+    /// Set when the corner is replaced, telling the worker thread to exit
+    pub stop_flag: AtomicBool,
+    /// The worker thread, for waking it up
+    thread: OnceLock<Thread>,
+    /// Handle to the worker thread, taken by `join`
+    handle: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl CornerWorker {
+    /// Attaches the worker thread, can only be called once
+    pub fn start(&self, handle: JoinHandle<()>) {
+        self.thread
+            .set(handle.thread().clone())
+            .expect("the worker thread is only started once");
+        *self.handle.lock().unwrap() = Some(handle);
+    }
+
+    /// Wakes the worker thread up to perform the corner's action. Returns `false` without waking it
+    /// if it is already armed, so the action never runs twice for one entry.
+    pub fn arm(&self) -> bool {
+        // The release half pairs with the worker's `Acquire` load, so the worker sees everything
+        // written before this even if it wakes up spuriously instead of via `unpark`
+        if self
+            .arm_flag
+            .compare_exchange(false, true, Ordering::AcqRel, Ordering::Relaxed)
+            .is_err()
+        {
+            return false;
+        }
+        // Only cleared once armed, so a cancellation for the activation that is already running
+        // isn't lost. The worker checks it after the delay, long after this store.
+        self.cancel_flag.store(false, Ordering::Release);
+        self.unpark();
+        true
+    }
+
+    /// Cancels a pending activation and tells the worker thread to exit once it has finished any
+    /// running action
+    pub fn shut_down(&self) {
+        self.cancel_flag.store(true, Ordering::Release);
+        self.stop_flag.store(true, Ordering::Release);
+        self.unpark();
+    }
+
+    /// Waits for the worker thread to exit after `shut_down`
+    pub fn join(&self) {
+        let handle = self.handle.lock().unwrap().take();
+        if let Some(handle) = handle {
+            let _ = handle.join();
+        }
+    }
+
+    /// Wakes the worker thread up, if it was started
+    fn unpark(&self) {
+        if let Some(thread) = self.thread.get() {
+            thread.unpark();
+        }
+    }
+}
+
+/// Shape of the on-screen part of a hot corner
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ZoneShape {
+    /// The whole `size` by `size` square
+    #[default]
+    Rectangle,
+    /// Only the half of the square closest to the corner, points whose distance to both edges
+    /// adds up to less than `size`
+    Triangle,
+}
+
+/// Side of a corner the cursor came from when it entered it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Direction {
+    /// Moving right, coming from the left
+    FromLeft,
+    /// Moving left, coming from the right
+    FromRight,
+    /// Moving down, coming from above
+    FromAbove,
+    /// Moving up, coming from below
+    FromBelow,
+}
+
+impl Direction {
+    /// Direction of the move from `from` to `to`, by whichever axis it moved along more. `None`
+    /// if the cursor didn't move.
+    pub fn of_move(from: POINT, to: POINT) -> Option<Self> {
+        let dx = i64::from(to.x) - i64::from(from.x);
+        let dy = i64::from(to.y) - i64::from(from.y);
+        if dx == 0 && dy == 0 {
+            None
+        } else if dx.abs() >= dy.abs() {
+            Some(if dx > 0 {
+                Direction::FromLeft
+            } else {
+                Direction::FromRight
+            })
+        } else if dy > 0 {
+            Some(Direction::FromAbove)
+        } else {
+            Some(Direction::FromBelow)
+        }
+    }
+}
+
+/// How many of a corner's actions succeeded and failed
+#[derive(Debug, Default)]
+pub struct ActionResultCounter {
+    /// Actions that succeeded
+    succeeded: AtomicU64,
+    /// Actions that failed
+    failed: AtomicU64,
+}
+
+impl ActionResultCounter {
+    /// Counts an action that succeeded if `ok` is set, or failed otherwise
+    pub fn record(&self, ok: bool) {
+        let counter = if ok { &self.succeeded } else { &self.failed };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// How many actions succeeded
+    pub fn succeeded(&self) -> u64 {
+        self.succeeded.load(Ordering::Relaxed)
+    }
+
+    /// How many actions failed
+    pub fn failed(&self) -> u64 {
+        self.failed.load(Ordering::Relaxed)
+    }
+}
+
+/// Activation state for a single corner, shared between the mouse hook and the corner's worker
+/// thread
+#[derive(Debug)]
+pub struct CornerState {
+    /// Which corner of its monitor this is
+    pub corner: Corner,
+    /// Rectangle the cursor has to enter to activate the corner
+    pub rect: RECT,
+    /// Shape of the corner inside `rect`
+    pub shape: ZoneShape,
+    /// Bounds of the monitor the corner is on
+    pub monitor: RECT,
+    /// Size of the on-screen part of the corner in physical pixels
+    pub size: u32,
+    /// The thread that performs the corner's action
+    pub worker: CornerWorker,
+    /// Whether the cursor is inside the corner and its entry was handled, either by arming the
+    /// worker or by ignoring it until the cursor leaves. Only accessed from the mouse hook, so
+    /// `Relaxed` is enough.
+    pub still_hot: AtomicBool,
+    /// Entries faster than this many pixels per millisecond don't activate the corner
+    pub max_entry_velocity: Option<f64>,
+    /// Only entries from these directions activate the corner, any if empty
+    pub entry_directions: Vec<Direction>,
+    /// How long after an activation the corner can't activate again, in milliseconds
+    pub cooldown_ms: u64,
+    /// Results of the corner's actions, not counting simulated ones
+    pub action_results: ActionResultCounter,
+    /// How to retry input the action couldn't send, if at all
+    pub send_input_retry: Option<RetryPolicy>,
+    /// `GetTickCount64` value of the last activation, 0 if the corner never activated
+    last_activation: AtomicU64,
+    /// How many times the corner activated
+    activations: AtomicU64,
+    /// Whether the previous mouse event was inside the corner, whether or not it activated
+    inside: AtomicBool,
+    /// Cursor position of the last mouse event outside the corner, packed with `pack_point`
+    last_outside_point: AtomicU64,
+    /// Timestamp of the last mouse event outside the corner, in milliseconds
+    last_outside_time: AtomicU32,
+}
+
+impl CornerState {
+    /// Creates the state for a corner zone with no worker thread attached yet
+    pub fn new(zone: &HotZone<'_>) -> Self {
+        let config = zone.config;
+        Self {
+            corner: config.position,
+            rect: zone.rect,
+            shape: config.shape,
+            monitor: zone.monitor,
+            size: zone.size,
+            worker: CornerWorker::default(),
+            still_hot: AtomicBool::new(false),
+            max_entry_velocity: config.max_entry_velocity,
+            entry_directions: config.entry_directions.clone(),
+            cooldown_ms: config.cooldown_ms,
+            action_results: ActionResultCounter::default(),
+            send_input_retry: zone.send_input_retry,
+            last_activation: AtomicU64::new(0),
+            activations: AtomicU64::new(0),
+            inside: AtomicBool::new(false),
+            last_outside_point: AtomicU64::new(0),
+            last_outside_time: AtomicU32::new(0),
+        }
+    }
+
+    /// Whether `pt` is inside the corner
+    pub fn contains(&self, pt: POINT) -> bool {
+        unsafe { PtInRect(&raw const self.rect, pt) }.as_bool()
+            && match self.shape {
+                ZoneShape::Rectangle => true,
+                ZoneShape::Triangle => {
+                    pt_in_triangle_corner(self.corner, self.size, &self.monitor, pt)
+                }
+            }
+    }
+
+    /// Records the position and timestamp of a mouse event outside the corner. Only called from
+    /// the mouse hook.
+    pub fn record_outside(&self, pt: POINT, time: u32) {
+        self.last_outside_point
+            .store(pack_point(pt), Ordering::Relaxed);
+        self.last_outside_time.store(time, Ordering::Relaxed);
+        self.inside.store(false, Ordering::Relaxed);
+    }
+
+    /// Records a mouse event inside the corner, returning whether the cursor just entered it
+    pub fn record_inside(&self) -> bool {
+        !self.inside.swap(true, Ordering::Relaxed)
+    }
+
+    /// Position and timestamp of the last mouse event outside the corner
+    pub fn last_outside(&self) -> (POINT, u32) {
+        (
+            unpack_point(self.last_outside_point.load(Ordering::Relaxed)),
+            self.last_outside_time.load(Ordering::Relaxed),
+        )
+    }
+
+    /// Remembers that the corner's action just ran
+    pub fn mark_activated(&self) {
+        let now = unsafe { GetTickCount64() };
+        self.last_activation.store(now, Ordering::Release);
+        self.activations.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// How many times the corner activated since it was created
+    pub fn activations(&self) -> u64 {
+        self.activations.load(Ordering::Relaxed)
+    }
+
+    /// Whether the corner activated less than `cooldown_ms` ago
+    pub fn cooling_down(&self) -> bool {
+        let last = self.last_activation.load(Ordering::Acquire);
+        let now = unsafe { GetTickCount64() };
+        last != 0 && now.saturating_sub(last) < self.cooldown_ms
+    }
+
+    /// Whether the cursor moving from `from`, its last position outside the corner, to `to`
+    /// entered from one of the `entry_directions`
+    pub fn entered_from_allowed_direction(&self, from: POINT, to: POINT) -> bool {
+        self.entry_directions.is_empty()
+            || Direction::of_move(from, to)
+                .is_none_or(|direction| self.entry_directions.contains(&direction))
+    }
+
+    /// Whether the cursor moved from `from` to `to` faster than `max_entry_velocity` allows
+    pub fn entered_too_fast(&self, from: (POINT, u32), to: (POINT, u32)) -> bool {
+        let Some(max) = self.max_entry_velocity else {
+            return false;
+        };
+        entry_velocity(from, to).is_some_and(|velocity| velocity > max)
+    }
+}
+
+/// Speed of the cursor between two mouse events in pixels per millisecond, or `None` if both
+/// events have the same timestamp
+fn entry_velocity((from, from_time): (POINT, u32), (to, to_time): (POINT, u32)) -> Option<f64> {
+    // Event timestamps wrap around after ~49.7 days
+    let elapsed = to_time.wrapping_sub(from_time);
+    if elapsed == 0 {
+        return None;
+    }
+
+    let dx = f64::from(to.x) - f64::from(from.x);
+    let dy = f64::from(to.y) - f64::from(from.y);
+    Some(dx.hypot(dy) / f64::from(elapsed))
+}
+
+/// Packs a point into a single `u64` so it can be stored atomically
+pub fn pack_point(pt: POINT) -> u64 {
+    // Reinterpreting the bits is intended, `unpack_point` reverses it
+    #[allow(clippy::cast_sign_loss)]
+    let (x, y) = (u64::from(pt.x as u32), u64::from(pt.y as u32));
+    (x << 32) | y
+}
+
+/// Unpacks a point packed with `pack_point`
+pub fn unpack_point(packed: u64) -> POINT {
+    // Truncation and reinterpreting the bits is intended, see `pack_point`
+    #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+    POINT {
+        x: (packed >> 32) as u32 as i32,
+        y: packed as u32 as i32,
+    }
+}
+
+/// Builds the hot rectangle for `corner` on `monitor`.
+///
+/// The rectangle covers `size` pixels at `DEFAULT_DPI`, scaled to the monitor's DPI, on each axis
+/// inside the monitor bounds and extends `OVERSHOOT` pixels past the edges. Edge zones span the
+/// whole length of their edge and only extend past the edge they are on. Like every `RECT` checked
+/// with `PtInRect`, it includes its left and top edges but not its right and bottom ones, so a 20
+/// pixel top-left corner covers `(19, 19)` but not `(20, 20)`.
+pub fn compute_corner_rect(corner: Corner, size: u32, monitor: &MonitorInfo) -> RECT {
+    let bounds = monitor.bounds;
+    let size = i32::try_from(scale_for_dpi(size, monitor.dpi)).unwrap_or(i32::MAX);
+
+    let (left, right) = if corner.is_left() {
+        (
+            bounds.left.saturating_sub(OVERSHOOT),
+            bounds.left.saturating_add(size),
+        )
+    } else if corner.is_right() {
+        (
+            bounds.right.saturating_sub(size),
+            bounds.right.saturating_add(OVERSHOOT),
+        )
+    } else {
+        (bounds.left, bounds.right)
+    };
+    let (top, bottom) = if corner.is_top() {
+        (
+            bounds.top.saturating_sub(OVERSHOOT),
+            bounds.top.saturating_add(size),
+        )
+    } else if corner.is_bottom() {
+        (
+            bounds.bottom.saturating_sub(size),
+            bounds.bottom.saturating_add(OVERSHOOT),
+        )
+    } else {
+        (bounds.top, bounds.bottom)
+    };
+
+    RECT {
+        left,
+        top,
+        right,
+        bottom,
+    }
+}
+
+/// Whether `pt` is closer to `corner` of the monitor with `bounds` than `size` pixels, counting
+/// the distance to both edges together.
+///
+/// Points past the edges count as being on them, so the overshoot of a corner stays hot. Edges
+/// only have one distance, so their triangle is the same as their rectangle.
+pub fn pt_in_triangle_corner(corner: Corner, size: u32, bounds: &RECT, pt: POINT) -> bool {
+    let (x, y) = (i64::from(pt.x), i64::from(pt.y));
+    // The corner pixel itself is at distance 0, the right and bottom edges are exclusive
+    let dx = if corner.is_left() {
+        x - i64::from(bounds.left)
+    } else if corner.is_right() {
+        i64::from(bounds.right) - 1 - x
+    } else {
+        0
+    };
+    let dy = if corner.is_top() {
+        y - i64::from(bounds.top)
+    } else if corner.is_bottom() {
+        i64::from(bounds.bottom) - 1 - y
+    } else {
+        0
+    };
+    dx.max(0) + dy.max(0) < i64::from(size)
+}
+
+/// Converts `size` pixels at `DEFAULT_DPI` to physical pixels at `dpi`
+pub fn scale_for_dpi(size: u32, dpi: u32) -> u32 {
+    let scaled = u64::from(size) * u64::from(dpi) / u64::from(DEFAULT_DPI);
+    u32::try_from(scaled).unwrap_or(u32::MAX)
+}
+
+/// Pulls the off-screen parts of a corner rect on the monitor with `bounds` back to the monitor's
+/// edges wherever they would reach into one of the `others` monitors.
+///
+/// Without this, the overshoot of e.g. a top-right corner would cover the top-left corner of the
+/// monitor to its right.
+pub fn trim_overshoot(rect: &mut RECT, corner: Corner, bounds: &RECT, others: &[RECT]) {
+    let reaches_into_others = |part: RECT| others.iter().any(|other| overlaps(&part, other));
+
+    if corner.is_left()
+        && reaches_into_others(RECT {
+            right: bounds.left,
+            ..*rect
+        })
+    {
+        rect.left = bounds.left;
+    }
+    if corner.is_right()
+        && reaches_into_others(RECT {
+            left: bounds.right,
+            ..*rect
+        })
+    {
+        rect.right = bounds.right;
+    }
+    if corner.is_top()
+        && reaches_into_others(RECT {
+            bottom: bounds.top,
+            ..*rect
+        })
+    {
+        rect.top = bounds.top;
+    }
+    if corner.is_bottom()
+        && reaches_into_others(RECT {
+            top: bounds.bottom,
+            ..*rect
+        })
+    {
+        rect.bottom = bounds.bottom;
+    }
+}
+
+/// Whether two rectangles share any pixels
+fn overlaps(a: &RECT, b: &RECT) -> bool {
+    a.left < b.right && b.left < a.right && a.top < b.bottom && b.top < a.bottom
+}
+
+/// A corner zone on one of the monitors, before its worker thread is started
+#[derive(Debug)]
+pub struct HotZone<'a> {
+    /// Configuration of the corner
+    pub config: &'a CornerConfig,
+    /// Hot rectangle in screen coordinates
+    pub rect: RECT,
+    /// Bounds of the monitor the zone is on
+    pub monitor: RECT,
+    /// Size of the on-screen part of the zone in physical pixels
+    pub size: u32,
+    /// `Config::send_input_retry`
+    pub send_input_retry: Option<RetryPolicy>,
+}
+
+/// Computes the zones of profile `profile` in `config` for the monitors attached right now.
+///
+/// Zones only exist for monitors that are attached, so the corners of a removed monitor stay
+/// inactive until it comes back and the zones are rebuilt.
+pub fn rebuild_corner_zones(config: &Config, profile: usize) -> Result<Vec<HotZone<'_>>, String> {
+    let monitors = enumerate_monitors();
+    let mut zones = Vec::new();
+    for monitor in &monitors {
+        let others: Vec<RECT> = monitors
+            .iter()
+            .filter(|other| other.index != monitor.index)
+            .map(|other| other.bounds)
+            .collect();
+
+        for corner in Corner::ALL {
+            let Some(corner_config) = config.corner(profile, corner) else {
+                continue;
+            };
+            let size = config::validate_corner_size(config.corner_size(corner_config), monitor)
+                .ok_or_else(|| "Corner size must be greater than zero".to_owned())?;
+            let mut rect = compute_corner_rect(corner, size, monitor);
+            trim_overshoot(&mut rect, corner, &monitor.bounds, &others);
+            zones.push(HotZone {
+                config: corner_config,
+                rect,
+                monitor: monitor.bounds,
+                size: scale_for_dpi(size, monitor.dpi),
+                send_input_retry: config.send_input_retry(),
+            });
+        }
+    }
+
+    Ok(zones)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A 1920x1080 primary monitor at 100% scaling
+    fn monitor() -> MonitorInfo {
+        MonitorInfo {
+            index: 0,
+            device_name: String::new(),
+            bounds: RECT {
+                left: 0,
+                top: 0,
+                right: 1920,
+                bottom: 1080,
+            },
+            work_area: RECT {
+                left: 0,
+                top: 0,
+                right: 1920,
+                bottom: 1040,
+            },
+            dpi: DEFAULT_DPI,
+            is_primary: true,
+        }
+    }
+
+    /// State of the default top-left corner on `monitor()`
+    fn top_left(config: &Config) -> CornerState {
+        let corner = &config.corners[0];
+        let size = config.corner_size(corner);
+        CornerState::new(&HotZone {
+            config: corner,
+            rect: compute_corner_rect(Corner::TopLeft, size, &monitor()),
+            monitor: monitor().bounds,
+            size,
+            send_input_retry: None,
+        })
+    }
+
+    /// Whether `PtInRect` counts `(x, y)` as inside `rect`
+    fn in_rect(rect: &RECT, x: i32, y: i32) -> bool {
+        unsafe { PtInRect(rect, POINT { x, y }) }.as_bool()
+    }
+
+    #[test]
+    fn corner_rect_includes_its_left_and_top_edges() {
+        let rect = compute_corner_rect(Corner::TopLeft, DEFAULT_CORNER_SIZE, &monitor());
+        assert!(in_rect(&rect, 0, 0));
+        assert!(in_rect(&rect, 19, 19));
+    }
+
+    #[test]
+    fn corner_rect_excludes_its_right_and_bottom_edges() {
+        let rect = compute_corner_rect(Corner::TopLeft, DEFAULT_CORNER_SIZE, &monitor());
+        assert!(!in_rect(&rect, 20, 20));
+        assert!(!in_rect(&rect, 19, 20));
+        assert!(!in_rect(&rect, 20, 19));
+    }
+
+    #[test]
+    fn corner_rect_extends_past_the_screen() {
+        let rect = compute_corner_rect(Corner::TopLeft, DEFAULT_CORNER_SIZE, &monitor());
+        assert!(in_rect(&rect, -1, -1));
+        assert!(in_rect(&rect, -OVERSHOOT, -OVERSHOOT));
+        assert!(!in_rect(&rect, -OVERSHOOT - 1, -OVERSHOOT - 1));
+        assert!(!in_rect(&rect, i32::MIN, i32::MIN));
+    }
+
+    #[test]
+    fn only_the_first_event_inside_is_an_entry() {
+        let config = Config::default();
+        let state = top_left(&config);
+        state.record_outside(POINT { x: 100, y: 100 }, 1);
+        assert!(state.record_inside());
+        assert!(!state.record_inside());
+
+        state.record_outside(POINT { x: 100, y: 100 }, 2);
+        assert!(state.record_inside());
+    }
+
+    #[test]
+    fn last_outside_ignores_events_inside() {
+        let config = Config::default();
+        let state = top_left(&config);
+        state.record_outside(POINT { x: 100, y: 5 }, 1);
+        state.record_inside();
+        state.record_inside();
+
+        let (pt, time) = state.last_outside();
+        assert_eq!((pt.x, pt.y, time), (100, 5, 1));
+    }
+
+    #[test]
+    fn action_results_start_at_zero() {
+        let results = ActionResultCounter::default();
+        assert_eq!((results.succeeded(), results.failed()), (0, 0));
+    }
+
+    #[test]
+    fn action_results_count_successes_and_failures_apart() {
+        let results = ActionResultCounter::default();
+        results.record(true);
+        results.record(false);
+        results.record(true);
+        assert_eq!((results.succeeded(), results.failed()), (2, 1));
+    }
+}
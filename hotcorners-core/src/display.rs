@@ -0,0 +1,463 @@
+//! Display configuration: topology and HDR through the CCD (connecting and configuring displays)
+//! API, orientation and resolution through the older GDI display settings
+
+use std::{thread, time::Duration};
+
+use serde::{Deserialize, Serialize};
+use tracing::{debug, error, warn};
+use windows::{
+    core::{Error, Result, HRESULT, HSTRING},
+    Win32::{
+        Devices::Display::{
+            DisplayConfigGetDeviceInfo, DisplayConfigSetDeviceInfo, GetDisplayConfigBufferSizes,
+            QueryDisplayConfig, SetDisplayConfig,
+            DISPLAYCONFIG_DEVICE_INFO_GET_ADVANCED_COLOR_INFO, DISPLAYCONFIG_DEVICE_INFO_HEADER,
+            DISPLAYCONFIG_DEVICE_INFO_SET_ADVANCED_COLOR_STATE, DISPLAYCONFIG_DEVICE_INFO_TYPE,
+            DISPLAYCONFIG_GET_ADVANCED_COLOR_INFO, DISPLAYCONFIG_MODE_INFO,
+            DISPLAYCONFIG_MODE_INFO_TYPE_SOURCE, DISPLAYCONFIG_PATH_INFO,
+            DISPLAYCONFIG_SET_ADVANCED_COLOR_STATE, DISPLAYCONFIG_SET_ADVANCED_COLOR_STATE_0,
+            QDC_ALL_PATHS, QDC_ONLY_ACTIVE_PATHS, QUERY_DISPLAY_CONFIG_FLAGS, SDC_APPLY,
+            SDC_TOPOLOGY_CLONE, SDC_TOPOLOGY_EXTEND, SDC_TOPOLOGY_EXTERNAL, SDC_TOPOLOGY_INTERNAL,
+        },
+        Foundation::{ERROR_NOT_FOUND, ERROR_NOT_SUPPORTED, HWND, LUID, WIN32_ERROR},
+        Graphics::Gdi::{
+            ChangeDisplaySettingsExW, EnumDisplaySettingsExW, EnumDisplaySettingsW, CDS_TEST,
+            CDS_TYPE, CDS_UPDATEREGISTRY, DEVMODEW, DEVMODE_DISPLAY_ORIENTATION,
+            DISP_CHANGE_SUCCESSFUL, DMDO_180, DMDO_270, DMDO_90, DMDO_DEFAULT,
+            DM_DISPLAYORIENTATION, DM_PELSHEIGHT, DM_PELSWIDTH, EDS_ROTATEDMODE,
+            ENUM_CURRENT_SETTINGS, ENUM_DISPLAY_SETTINGS_MODE,
+        },
+    },
+};
+
+use crate::{actions::ActionError, monitors::enumerate_monitors};
+
+/// Rotations `rotate-display` accepts, in degrees clockwise
+pub const ROTATIONS: [u32; 4] = [0, 90, 180, 270];
+
+/// How to use multiple displays, like the options of `Win+P`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DisplayProjectionMode {
+    /// Extend the desktop across every display
+    Extend,
+    /// Show the same picture on every display
+    Duplicate,
+    /// Only use the built-in display
+    Internal,
+    /// Only use the external displays
+    External,
+}
+
+/// Switches to `mode`, which Windows remembers as this set of displays' topology.
+///
+/// Does nothing but log a warning if fewer than two displays are connected.
+pub fn set_projection_mode(mode: DisplayProjectionMode) -> Result<()> {
+    let displays = connected_displays()?;
+    if displays < 2 {
+        warn!(
+            ?mode,
+            displays, "Only one display is connected, not changing the display mode"
+        );
+        return Ok(());
+    }
+
+    let topology = match mode {
+        DisplayProjectionMode::Extend => SDC_TOPOLOGY_EXTEND,
+        DisplayProjectionMode::Duplicate => SDC_TOPOLOGY_CLONE,
+        DisplayProjectionMode::Internal => SDC_TOPOLOGY_INTERNAL,
+        DisplayProjectionMode::External => SDC_TOPOLOGY_EXTERNAL,
+    };
+    // The topology flags apply the configuration Windows saved for this set of displays, which it
+    // keeps in its database without `SDC_SAVE_TO_DATABASE`. That flag is only valid together
+    // with a supplied configuration.
+    let result = unsafe { SetDisplayConfig(None, None, SDC_APPLY | topology) };
+    // Win32 error codes are never negative
+    check(result)?;
+    debug!(?mode, "Changed the display mode");
+    Ok(())
+}
+
+/// Turns HDR on the primary display on or off, whichever it isn't, and checks that Windows did
+pub fn toggle_hdr() -> Result<()> {
+    let target = primary_target()?;
+    let before = hdr_state(target)?;
+    if !before.supported {
+        return Err(Error::new(
+            HRESULT::from_win32(ERROR_NOT_SUPPORTED.0),
+            "The primary display doesn't support HDR",
+        ));
+    }
+
+    let state = DISPLAYCONFIG_SET_ADVANCED_COLOR_STATE {
+        header: target.header::<DISPLAYCONFIG_SET_ADVANCED_COLOR_STATE>(
+            DISPLAYCONFIG_DEVICE_INFO_SET_ADVANCED_COLOR_STATE,
+        ),
+        // The lowest bit is `enableAdvancedColor`
+        Anonymous: DISPLAYCONFIG_SET_ADVANCED_COLOR_STATE_0 {
+            value: u32::from(!before.enabled),
+        },
+    };
+    check(unsafe { DisplayConfigSetDeviceInfo(&raw const state.header) })?;
+
+    let after = hdr_state(target)?;
+    if after.enabled == before.enabled {
+        return Err(Error::new(
+            HRESULT::from_win32(ERROR_NOT_SUPPORTED.0),
+            "Windows didn't change the HDR state of the primary display",
+        ));
+    }
+    debug!(enabled = after.enabled, "Toggled HDR");
+    Ok(())
+}
+
+/// Whether the primary display supports HDR, `false` if that can't be found out
+pub fn is_hdr_supported() -> bool {
+    primary_target()
+        .and_then(hdr_state)
+        .is_ok_and(|state| state.supported)
+}
+
+/// A monitor connected to a display adapter
+#[derive(Debug, Clone, Copy)]
+struct Target {
+    /// Adapter the monitor is connected to
+    adapter: LUID,
+    /// ID of the monitor on its adapter
+    id: u32,
+}
+
+impl Target {
+    /// Header of a `DisplayConfigGetDeviceInfo` or `DisplayConfigSetDeviceInfo` packet of type `T`
+    /// about this monitor
+    fn header<T>(self, kind: DISPLAYCONFIG_DEVICE_INFO_TYPE) -> DISPLAYCONFIG_DEVICE_INFO_HEADER {
+        DISPLAYCONFIG_DEVICE_INFO_HEADER {
+            r#type: kind,
+            // The packets are a few dozen bytes
+            #[allow(clippy::cast_possible_truncation)]
+            size: std::mem::size_of::<T>() as u32,
+            adapterId: self.adapter,
+            id: self.id,
+        }
+    }
+}
+
+/// Advanced color state of a monitor
+#[derive(Debug, Clone, Copy)]
+struct HdrState {
+    /// Whether the monitor supports HDR
+    supported: bool,
+    /// Whether HDR is on
+    enabled: bool,
+}
+
+/// The monitor showing the primary display, whose desktop starts at (0, 0)
+fn primary_target() -> Result<Target> {
+    let (paths, modes) = query_display_config(QDC_ONLY_ACTIVE_PATHS)?;
+    paths
+        .iter()
+        .find(|path| {
+            // Without `QDC_VIRTUAL_MODE_AWARE` the union holds a plain mode index
+            let index = unsafe { path.sourceInfo.Anonymous.modeInfoIdx } as usize;
+            modes.get(index).is_some_and(|mode| {
+                mode.infoType == DISPLAYCONFIG_MODE_INFO_TYPE_SOURCE && {
+                    let position = unsafe { mode.Anonymous.sourceMode.position };
+                    position.x == 0 && position.y == 0
+                }
+            })
+        })
+        .map(|path| Target {
+            adapter: path.targetInfo.adapterId,
+            id: path.targetInfo.id,
+        })
+        .ok_or_else(|| {
+            Error::new(
+                HRESULT::from_win32(ERROR_NOT_FOUND.0),
+                "No display shows the primary desktop",
+            )
+        })
+}
+
+/// Advanced color state of `target`
+fn hdr_state(target: Target) -> Result<HdrState> {
+    let mut info = DISPLAYCONFIG_GET_ADVANCED_COLOR_INFO {
+        header: target.header::<DISPLAYCONFIG_GET_ADVANCED_COLOR_INFO>(
+            DISPLAYCONFIG_DEVICE_INFO_GET_ADVANCED_COLOR_INFO,
+        ),
+        ..Default::default()
+    };
+    check(unsafe { DisplayConfigGetDeviceInfo(&raw mut info.header) })?;
+    // `advancedColorSupported` and `advancedColorEnabled` are the two lowest bits
+    let flags = unsafe { info.Anonymous.value };
+    Ok(HdrState {
+        supported: flags & 1 != 0,
+        enabled: flags & 2 != 0,
+    })
+}
+
+/// Rotates the display `monitor_index` by `degrees` clockwise from its natural orientation, one
+/// of [`ROTATIONS`].
+///
+/// Displays are numbered like [`MonitorInfo::index`](crate::monitors::MonitorInfo::index),
+/// `None` is the primary one.
+pub fn rotate(degrees: u32, monitor_index: Option<u32>) -> std::result::Result<(), ActionError> {
+    let orientation = match degrees {
+        0 => DMDO_DEFAULT,
+        90 => DMDO_90,
+        180 => DMDO_180,
+        270 => DMDO_270,
+        _ => {
+            return Err(ActionError::NotFound {
+                what: format!("display orientation for {degrees} degrees"),
+            })
+        }
+    };
+    let device = enumerate_monitors()
+        .into_iter()
+        .find(|monitor| match monitor_index {
+            Some(wanted) => usize::try_from(wanted).is_ok_and(|wanted| wanted == monitor.index),
+            None => monitor.is_primary,
+        })
+        .map(|monitor| monitor.device_name)
+        .ok_or_else(|| ActionError::NotFound {
+            what: match monitor_index {
+                Some(index) => format!("display {index}"),
+                None => "primary display".to_owned(),
+            },
+        })?;
+
+    let mut mode = current_mode(&device)?;
+    let current = unsafe { mode.Anonymous1.Anonymous2.dmDisplayOrientation };
+    if current == orientation {
+        return Ok(());
+    }
+    // Portrait and landscape orientations swap the width and height
+    if is_portrait(current) != is_portrait(orientation) {
+        std::mem::swap(&mut mode.dmPelsWidth, &mut mode.dmPelsHeight);
+    }
+    let supported = display_modes(&device).iter().any(|supported| {
+        supported.dmPelsWidth == mode.dmPelsWidth
+            && supported.dmPelsHeight == mode.dmPelsHeight
+            && unsafe { supported.Anonymous1.Anonymous2.dmDisplayOrientation } == orientation
+    });
+    if !supported {
+        return Err(ActionError::NotFound {
+            what: format!("mode of {device} rotated by {degrees} degrees"),
+        });
+    }
+
+    mode.Anonymous1.Anonymous2.dmDisplayOrientation = orientation;
+    mode.dmFields = DM_DISPLAYORIENTATION | DM_PELSWIDTH | DM_PELSHEIGHT;
+    change_mode(&device, &mode, CDS_UPDATEREGISTRY)?;
+    debug!(device, degrees, "Rotated the display");
+    Ok(())
+}
+
+/// An empty `DEVMODEW` for the display settings functions to fill in
+fn empty_mode() -> DEVMODEW {
+    DEVMODEW {
+        // `size_of::<DEVMODEW>()` is a couple of hundred bytes
+        #[allow(clippy::cast_possible_truncation)]
+        dmSize: std::mem::size_of::<DEVMODEW>() as u16,
+        ..Default::default()
+    }
+}
+
+/// The mode `device` currently uses
+fn current_mode(device: &str) -> std::result::Result<DEVMODEW, ActionError> {
+    let mut mode = empty_mode();
+    if unsafe { EnumDisplaySettingsW(&HSTRING::from(device), ENUM_CURRENT_SETTINGS, &raw mut mode) }
+        .as_bool()
+    {
+        Ok(mode)
+    } else {
+        Err(ActionError::NotFound {
+            what: format!("current mode of {device}"),
+        })
+    }
+}
+
+/// Every mode `device` supports, in every orientation
+fn display_modes(device: &str) -> Vec<DEVMODEW> {
+    let name = HSTRING::from(device);
+    let mut modes = Vec::new();
+    for index in 0.. {
+        let mut mode = empty_mode();
+        let found = unsafe {
+            EnumDisplaySettingsExW(
+                &name,
+                ENUM_DISPLAY_SETTINGS_MODE(index),
+                &raw mut mode,
+                EDS_ROTATEDMODE,
+            )
+        };
+        if !found.as_bool() {
+            break;
+        }
+        modes.push(mode);
+    }
+    modes
+}
+
+/// Switches `device` to `mode`, after checking that it can with `CDS_TEST`
+fn change_mode(
+    device: &str,
+    mode: &DEVMODEW,
+    flags: CDS_TYPE,
+) -> std::result::Result<(), ActionError> {
+    let name = HSTRING::from(device);
+    for flags in [CDS_TEST, flags] {
+        let result =
+            unsafe { ChangeDisplaySettingsExW(&name, Some(mode), HWND::default(), flags, None) };
+        if result != DISP_CHANGE_SUCCESSFUL {
+            // `DISP_CHANGE` failures are negative, the code keeps their bits
+            #[allow(clippy::cast_sign_loss)]
+            return Err(ActionError::Win32Failed {
+                code: result.0 as u32,
+                context: format!("change the mode of {device}"),
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Switches the display with the GDI device name `monitor_name`, or the primary one, to a
+/// resolution of `width` by `height` pixels.
+///
+/// With `revert_after` the change isn't saved, and the display switches back to its saved mode
+/// once that much time has passed.
+pub fn set_resolution(
+    width: u32,
+    height: u32,
+    monitor_name: Option<&str>,
+    revert_after: Option<Duration>,
+) -> std::result::Result<(), ActionError> {
+    let device = named_display(monitor_name)?;
+    let mut mode = current_mode(&device)?;
+    if mode.dmPelsWidth == width && mode.dmPelsHeight == height {
+        return Ok(());
+    }
+    mode.dmPelsWidth = width;
+    mode.dmPelsHeight = height;
+    mode.dmFields = DM_PELSWIDTH | DM_PELSHEIGHT;
+
+    let Some(delay) = revert_after else {
+        change_mode(&device, &mode, CDS_UPDATEREGISTRY)?;
+        debug!(device, width, height, "Changed the resolution");
+        return Ok(());
+    };
+    change_mode(&device, &mode, CDS_TYPE(0))?;
+    debug!(
+        device,
+        width,
+        height,
+        ?delay,
+        "Changed the resolution until the delay passes"
+    );
+    thread::spawn(move || {
+        thread::sleep(delay);
+        // Without a mode, the saved one is restored
+        let result = unsafe {
+            ChangeDisplaySettingsExW(
+                &HSTRING::from(&device),
+                None,
+                HWND::default(),
+                CDS_TYPE(0),
+                None,
+            )
+        };
+        if result == DISP_CHANGE_SUCCESSFUL {
+            debug!(device, "Restored the resolution");
+        } else {
+            error!(device, "Failed to restore the resolution: {}", result.0);
+        }
+    });
+    Ok(())
+}
+
+/// Whether the display with the GDI device name `monitor_name`, or the primary one, can show a
+/// resolution of `width` by `height` pixels in its current orientation
+pub fn supports_resolution(width: u32, height: u32, monitor_name: Option<&str>) -> bool {
+    let Ok(device) = named_display(monitor_name) else {
+        return false;
+    };
+    let Ok(current) = current_mode(&device) else {
+        return false;
+    };
+    let orientation = unsafe { current.Anonymous1.Anonymous2.dmDisplayOrientation };
+    display_modes(&device).iter().any(|mode| {
+        mode.dmPelsWidth == width
+            && mode.dmPelsHeight == height
+            && unsafe { mode.Anonymous1.Anonymous2.dmDisplayOrientation } == orientation
+    })
+}
+
+/// GDI device name of the display called `name`, ignoring case, or of the primary display
+fn named_display(name: Option<&str>) -> std::result::Result<String, ActionError> {
+    enumerate_monitors()
+        .into_iter()
+        .find(|monitor| match name {
+            Some(name) => monitor.device_name.eq_ignore_ascii_case(name),
+            None => monitor.is_primary,
+        })
+        .map(|monitor| monitor.device_name)
+        .ok_or_else(|| ActionError::NotFound {
+            what: match name {
+                Some(name) => format!("display {name}"),
+                None => "primary display".to_owned(),
+            },
+        })
+}
+
+/// Whether `orientation` is rotated by 90 or 270 degrees
+fn is_portrait(orientation: DEVMODE_DISPLAY_ORIENTATION) -> bool {
+    orientation == DMDO_90 || orientation == DMDO_270
+}
+
+/// Turns the Win32 error code the display configuration functions return into a `Result`
+fn check(result: i32) -> Result<()> {
+    // Win32 error codes are never negative
+    #[allow(clippy::cast_sign_loss)]
+    WIN32_ERROR(result as u32).ok()
+}
+
+/// Number of displays connected, whether Windows currently uses them or not
+fn connected_displays() -> Result<usize> {
+    let (paths, _) = query_display_config(QDC_ALL_PATHS)?;
+    // Every combination of source and target has a path, so targets repeat
+    let mut targets: Vec<_> = paths
+        .iter()
+        .filter(|path| path.targetInfo.targetAvailable.as_bool())
+        .map(|path| {
+            let adapter = path.targetInfo.adapterId;
+            (adapter.HighPart, adapter.LowPart, path.targetInfo.id)
+        })
+        .collect();
+    targets.sort_unstable();
+    targets.dedup();
+    Ok(targets.len())
+}
+
+/// The display paths and modes `flags` selects
+fn query_display_config(
+    flags: QUERY_DISPLAY_CONFIG_FLAGS,
+) -> Result<(Vec<DISPLAYCONFIG_PATH_INFO>, Vec<DISPLAYCONFIG_MODE_INFO>)> {
+    let (mut path_count, mut mode_count) = (0, 0);
+    unsafe { GetDisplayConfigBufferSizes(flags, &raw mut path_count, &raw mut mode_count) }.ok()?;
+    let mut paths = vec![DISPLAYCONFIG_PATH_INFO::default(); path_count as usize];
+    let mut modes = vec![DISPLAYCONFIG_MODE_INFO::default(); mode_count as usize];
+    unsafe {
+        QueryDisplayConfig(
+            flags,
+            &raw mut path_count,
+            paths.as_mut_ptr(),
+            &raw mut mode_count,
+            modes.as_mut_ptr(),
+            None,
+        )
+    }
+    .ok()?;
+    paths.truncate(path_count as usize);
+    modes.truncate(mode_count as usize);
+    Ok((paths, modes))
+}
@@ -0,0 +1,88 @@
+//! ETW (Event Tracing for Windows) provider, so xperf, `PerfView` and Windows Performance Recorder
+//! can record when the hook is installed, corners are entered and actions run, with precise
+//! timestamps.
+//!
+//! The provider is called `hotcorners`, its GUID is derived from that name, so e.g.
+//! `tracelog -guid *hotcorners` selects it. Events cost nothing while no session records them.
+
+use tracelogging as tlg;
+
+use crate::corners::Corner;
+
+tlg::define_provider!(PROVIDER, "hotcorners");
+
+/// Registers the provider, events before this are dropped
+pub fn register() {
+    // Safe in an executable, the provider is unregistered when the process exits at the latest
+    let result = unsafe { PROVIDER.register() };
+    if result != 0 {
+        tracing::warn!("Failed to register the ETW provider: {result}");
+    }
+}
+
+/// Unregisters the provider again
+pub fn unregister() {
+    PROVIDER.unregister();
+}
+
+/// The low-level mouse hook was installed
+pub fn hook_installed() {
+    tlg::write_event!(PROVIDER, "HookInstalled", level(Informational));
+}
+
+/// The low-level mouse hook was removed
+pub fn hook_removed() {
+    tlg::write_event!(PROVIDER, "HookRemoved", level(Informational));
+}
+
+/// The cursor entered `corner`
+pub fn corner_entered(corner: Corner) {
+    tlg::write_event!(
+        PROVIDER,
+        "CornerEntered",
+        level(Verbose),
+        str8("Corner", &corner.to_string()),
+    );
+}
+
+/// The worker of `corner` started waiting out the delay
+pub fn dwell_started(corner: Corner) {
+    tlg::write_event!(
+        PROVIDER,
+        "DwellStarted",
+        level(Verbose),
+        str8("Corner", &corner.to_string()),
+    );
+}
+
+/// The cursor left `corner` before the delay passed
+pub fn dwell_cancelled(corner: Corner) {
+    tlg::write_event!(
+        PROVIDER,
+        "DwellCancelled",
+        level(Verbose),
+        str8("Corner", &corner.to_string()),
+    );
+}
+
+/// `corner` started performing its action, called `action` in `config.toml`
+pub fn action_started(corner: Corner, action: &str) {
+    tlg::write_event!(
+        PROVIDER,
+        "ActionStarted",
+        level(Informational),
+        str8("Corner", &corner.to_string()),
+        str8("Action", action),
+    );
+}
+
+/// `corner` finished performing its action
+pub fn action_finished(corner: Corner, action: &str) {
+    tlg::write_event!(
+        PROVIDER,
+        "ActionFinished",
+        level(Informational),
+        str8("Corner", &corner.to_string()),
+        str8("Action", action),
+    );
+}
@@ -0,0 +1,117 @@
+//! Focus Assist, called Do Not Disturb on Windows 11, through the undocumented WNF state the
+//! quick settings use, or its Settings page if `ntdll.dll` doesn't export the WNF functions
+
+use std::ffi::c_void;
+
+use tracing::{debug, warn};
+use windows::{
+    core::{s, w},
+    Win32::{
+        Foundation::NTSTATUS,
+        System::LibraryLoader::{GetModuleHandleW, GetProcAddress},
+    },
+};
+
+use crate::actions::{shell_execute_uri, ActionError};
+
+/// Settings page with the Focus Assist options
+const SETTINGS_URI: &str = "ms-settings:quiethours";
+/// `WNF_SHEL_QUIETHOURS_ACTIVE_PROFILE_CHANGED`, the active Focus Assist profile
+const ACTIVE_PROFILE: u64 = 0x0D83_063E_A3BF_1C75;
+/// Profile with Focus Assist off
+const PROFILE_OFF: u32 = 0;
+/// Profile that only lets notifications from the priority list through, what the quick setting
+/// turns on
+const PROFILE_PRIORITY_ONLY: u32 = 1;
+
+/// `NtQueryWnfStateData`
+type QueryWnfStateData = unsafe extern "system" fn(
+    state_name: *const u64,
+    type_id: *const c_void,
+    explicit_scope: *const c_void,
+    change_stamp: *mut u32,
+    buffer: *mut c_void,
+    buffer_size: *mut u32,
+) -> NTSTATUS;
+/// `NtUpdateWnfStateData`
+type UpdateWnfStateData = unsafe extern "system" fn(
+    state_name: *const u64,
+    buffer: *const c_void,
+    length: u32,
+    type_id: *const c_void,
+    explicit_scope: *const c_void,
+    matching_change_stamp: u32,
+    check_stamp: u32,
+) -> NTSTATUS;
+
+/// Turns Focus Assist on, letting only priority notifications through, or off. Opens its
+/// Settings page instead if that isn't possible.
+pub fn toggle() -> Result<(), ActionError> {
+    match toggle_wnf() {
+        Ok(profile) => {
+            debug!(profile, "Changed the Focus Assist profile");
+            Ok(())
+        }
+        Err(e) => {
+            warn!("Failed to toggle Focus Assist, opening its settings instead: {e}");
+            shell_execute_uri(SETTINGS_URI)
+        }
+    }
+}
+
+/// Switches the active profile between off and priority only, returning the new one
+fn toggle_wnf() -> Result<u32, String> {
+    let (query, update) = wnf_functions().ok_or("ntdll.dll has no WNF functions")?;
+
+    let mut profile = 0u32;
+    let mut change_stamp = 0u32;
+    // `size_of::<u32>()` will never exceed u32::MAX
+    #[allow(clippy::cast_possible_truncation)]
+    let mut size = std::mem::size_of::<u32>() as u32;
+    unsafe {
+        query(
+            &ACTIVE_PROFILE,
+            std::ptr::null(),
+            std::ptr::null(),
+            &raw mut change_stamp,
+            (&raw mut profile).cast(),
+            &raw mut size,
+        )
+    }
+    .ok()
+    .map_err(|e| format!("NtQueryWnfStateData failed: {e}"))?;
+
+    let profile = if profile == PROFILE_OFF {
+        PROFILE_PRIORITY_ONLY
+    } else {
+        PROFILE_OFF
+    };
+    unsafe {
+        update(
+            &ACTIVE_PROFILE,
+            (&raw const profile).cast(),
+            size,
+            std::ptr::null(),
+            std::ptr::null(),
+            0,
+            0,
+        )
+    }
+    .ok()
+    .map_err(|e| format!("NtUpdateWnfStateData failed: {e}"))?;
+    Ok(profile)
+}
+
+/// `NtQueryWnfStateData` and `NtUpdateWnfStateData`, if `ntdll.dll` exports them
+fn wnf_functions() -> Option<(QueryWnfStateData, UpdateWnfStateData)> {
+    unsafe {
+        let ntdll = GetModuleHandleW(w!("ntdll.dll")).ok()?;
+        let query = GetProcAddress(ntdll, s!("NtQueryWnfStateData"))?;
+        let update = GetProcAddress(ntdll, s!("NtUpdateWnfStateData"))?;
+        // The exports have these signatures
+        Some((
+            std::mem::transmute::<unsafe extern "system" fn() -> isize, QueryWnfStateData>(query),
+            std::mem::transmute::<unsafe extern "system" fn() -> isize, UpdateWnfStateData>(update),
+        ))
+    }
+}
@@ -0,0 +1,58 @@
+//! Wrappers that release hooks and hotkeys when they go out of scope
+
+use tracing::error;
+use windows::{
+    core::Result,
+    Win32::{
+        Foundation::HWND,
+        UI::{
+            Input::KeyboardAndMouse::{
+                RegisterHotKey, UnregisterHotKey, HOT_KEY_MODIFIERS, VIRTUAL_KEY,
+            },
+            WindowsAndMessaging::{UnhookWindowsHookEx, HHOOK},
+        },
+    },
+};
+
+use crate::etw;
+
+/// Removes a hook installed with `SetWindowsHookExW` when dropped
+#[derive(Debug)]
+pub struct HookGuard(pub HHOOK);
+
+impl Drop for HookGuard {
+    fn drop(&mut self) {
+        if let Err(e) = unsafe { UnhookWindowsHookEx(self.0) } {
+            error!("Failed to remove the mouse hook: {e}");
+        }
+        etw::hook_removed();
+    }
+}
+
+/// A registered hotkey, unregistered again when dropped.
+///
+/// Must be dropped on the thread that registered it.
+#[derive(Debug)]
+pub struct HotkeyGuard(HWND, i32);
+
+impl HotkeyGuard {
+    /// Registers `modifiers` + `key` as hotkey `id` of `hwnd`, or of the calling thread if `hwnd`
+    /// is null
+    pub fn register(
+        hwnd: HWND,
+        id: i32,
+        modifiers: HOT_KEY_MODIFIERS,
+        key: VIRTUAL_KEY,
+    ) -> Result<Self> {
+        unsafe { RegisterHotKey(hwnd, id, modifiers, key.0.into())? };
+        Ok(Self(hwnd, id))
+    }
+}
+
+impl Drop for HotkeyGuard {
+    fn drop(&mut self) {
+        if let Err(e) = unsafe { UnregisterHotKey(self.0, self.1) } {
+            error!("Failed to unregister hotkey {}: {e}", self.1);
+        }
+    }
+}
@@ -0,0 +1,123 @@
+//! Global hotkeys and what they do
+
+use std::{collections::HashMap, iter};
+
+use tracing::warn;
+use windows::{
+    core::Result,
+    Win32::{
+        Foundation::{ERROR_HOTKEY_ALREADY_REGISTERED, HWND},
+        UI::Input::KeyboardAndMouse::{
+            HOT_KEY_MODIFIERS, MOD_ALT, MOD_CONTROL, MOD_SHIFT, MOD_WIN, VIRTUAL_KEY, VK_F1,
+        },
+    },
+};
+
+use crate::guard::HotkeyGuard;
+
+/// What a hotkey does when pressed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HotkeyAction {
+    /// Exit hotcorners
+    Exit,
+    /// Switch to the next profile
+    CycleProfile,
+}
+
+/// Hotkeys registered by the current thread, by ID.
+///
+/// Hotkeys are tied to the thread that registered them, so the registry must stay on that thread.
+#[derive(Debug, Default)]
+pub struct HotkeyRegistry {
+    /// ID of the last registered hotkey, IDs are never reused
+    last_id: i32,
+    /// Registered hotkeys, each unregistered when its guard is dropped
+    table: HashMap<i32, (HotkeyAction, HotkeyGuard)>,
+}
+
+impl HotkeyRegistry {
+    /// Registers `modifiers` + `key` to run `action`, or the first of `fallbacks` that is free if
+    /// another program uses that combination. Returns the ID of the new hotkey.
+    pub fn register(
+        &mut self,
+        modifiers: HOT_KEY_MODIFIERS,
+        key: VIRTUAL_KEY,
+        fallbacks: &[(HOT_KEY_MODIFIERS, VIRTUAL_KEY)],
+        action: HotkeyAction,
+    ) -> Result<i32> {
+        let id = self.last_id + 1;
+        let (guard, _) = try_register_hotkey(HWND::default(), id, modifiers, key, fallbacks)?;
+        self.last_id = id;
+        self.table.insert(id, (action, guard));
+        Ok(id)
+    }
+
+    /// What the hotkey with ID `id` does, `None` if it isn't registered
+    pub fn action(&self, id: i32) -> Option<HotkeyAction> {
+        self.table.get(&id).map(|&(action, _)| action)
+    }
+
+    /// Unregisters every hotkey
+    pub fn unregister_all(&mut self) {
+        self.table.clear();
+    }
+}
+
+/// Registers `modifiers` + `key` as hotkey `id` of `hwnd`. If another program already uses that
+/// combination, the `fallbacks` are tried in order.
+///
+/// Returns the registered hotkey and its key.
+pub fn try_register_hotkey(
+    hwnd: HWND,
+    id: i32,
+    modifiers: HOT_KEY_MODIFIERS,
+    key: VIRTUAL_KEY,
+    fallbacks: &[(HOT_KEY_MODIFIERS, VIRTUAL_KEY)],
+) -> Result<(HotkeyGuard, VIRTUAL_KEY)> {
+    let mut error = None;
+    for (i, (modifiers, key)) in iter::once((modifiers, key))
+        .chain(fallbacks.iter().copied())
+        .enumerate()
+    {
+        match HotkeyGuard::register(hwnd, id, modifiers, key) {
+            Ok(guard) => {
+                if i > 0 {
+                    warn!("Registered {} instead", describe_hotkey(modifiers, key));
+                }
+                return Ok((guard, key));
+            }
+            Err(e) if e.code() == ERROR_HOTKEY_ALREADY_REGISTERED.to_hresult() => {
+                warn!(
+                    "{} is already used by another program",
+                    describe_hotkey(modifiers, key)
+                );
+                error = Some(e);
+            }
+            Err(e) => return Err(e),
+        }
+    }
+
+    Err(error.expect("at least one combination was tried"))
+}
+
+/// Describes a key combination for messages, e.g. `Ctrl+Alt+C`
+pub fn describe_hotkey(modifiers: HOT_KEY_MODIFIERS, key: VIRTUAL_KEY) -> String {
+    let mut parts: Vec<String> = [
+        (MOD_CONTROL, "Ctrl"),
+        (MOD_ALT, "Alt"),
+        (MOD_SHIFT, "Shift"),
+        (MOD_WIN, "Win"),
+    ]
+    .into_iter()
+    .filter(|&(modifier, _)| modifiers.0 & modifier.0 != 0)
+    .map(|(_, name)| name.to_owned())
+    .collect();
+
+    // Digit and letter keys use their ASCII codes
+    parts.push(match key.0 {
+        vk @ (0x30..=0x39 | 0x41..=0x5A) => char::from_u32(vk.into()).unwrap_or('?').to_string(),
+        vk @ 0x70..=0x87 => format!("F{}", vk - VK_F1.0 + 1),
+        vk => format!("{vk:#04x}"),
+    });
+    parts.join("+")
+}
@@ -0,0 +1,286 @@
+//! Construction of the `INPUT` sequences passed to `SendInput`
+
+use windows::Win32::UI::{
+    Input::KeyboardAndMouse::{
+        INPUT, INPUT_0, INPUT_KEYBOARD, INPUT_MOUSE, KEYBDINPUT, KEYBD_EVENT_FLAGS,
+        KEYEVENTF_EXTENDEDKEY, KEYEVENTF_KEYUP, KEYEVENTF_UNICODE, MOUSEEVENTF_WHEEL, MOUSEINPUT,
+        VIRTUAL_KEY, VK_C, VK_CONTROL, VK_D, VK_DELETE, VK_DOWN, VK_END, VK_F4, VK_G, VK_HOME,
+        VK_INSERT, VK_LEFT, VK_LWIN, VK_NEXT, VK_PRIOR, VK_R, VK_RIGHT, VK_S, VK_UP,
+    },
+    WindowsAndMessaging::{LLMHF_INJECTED, MSLLHOOKSTRUCT, WHEEL_DELTA},
+};
+
+use crate::config::{ActionType, KeyEvent, KeySequenceEvent};
+
+/// `dwExtraInfo` of the mouse input hotcorners sends itself, so the hook can tell it apart from
+/// other injected input
+pub const HOTCORNERS_EXTRA_INFO: usize = 0x484F_5443;
+
+/// Mask and signature Windows puts in `dwExtraInfo` of mouse input synthesized from touch or pen
+/// input
+const TOUCH_SIGNATURE_MASK: usize = 0xFFFF_FF00;
+/// See `TOUCH_SIGNATURE_MASK`
+const TOUCH_SIGNATURE: usize = 0xFF51_5700;
+
+/// Whether a low-level mouse event was injected by another program or synthesized from touch or
+/// pen input, rather than coming from a mouse
+pub fn is_touch_or_injected(evt: &MSLLHOOKSTRUCT) -> bool {
+    if evt.dwExtraInfo == HOTCORNERS_EXTRA_INFO {
+        return false;
+    }
+    evt.flags & LLMHF_INJECTED != 0 || evt.dwExtraInfo & TOUCH_SIGNATURE_MASK == TOUCH_SIGNATURE
+}
+
+/// Builds a single keyboard `INPUT` event
+pub const fn keyboard_input(vk: VIRTUAL_KEY, flags: KEYBD_EVENT_FLAGS) -> INPUT {
+    INPUT {
+        r#type: INPUT_KEYBOARD,
+        Anonymous: INPUT_0 {
+            ki: KEYBDINPUT {
+                wVk: vk,
+                wScan: 0,
+                dwFlags: flags,
+                time: 0,
+                dwExtraInfo: 0,
+            },
+        },
+    }
+}
+
+/// Builds the input sequence for a key combination.
+///
+/// Every key is pressed in order and then released in reverse order, so earlier keys (usually
+/// modifiers) are held down while the later ones are pressed.
+pub fn build_key_inputs(keys: &[KeyEvent]) -> Vec<INPUT> {
+    let down = keys
+        .iter()
+        .map(|key| keyboard_input(VIRTUAL_KEY(key.vk), KEYBD_EVENT_FLAGS(key.flags)));
+    let up = keys.iter().rev().map(|key| {
+        keyboard_input(
+            VIRTUAL_KEY(key.vk),
+            KEYBD_EVENT_FLAGS(key.flags) | KEYEVENTF_KEYUP,
+        )
+    });
+
+    down.chain(up).collect()
+}
+
+/// Builds the input sequence of a `keys` action, one event per entry in the same order.
+///
+/// Keys with names that don't resolve are skipped, loading the configuration rejects them.
+pub fn build_keystroke_inputs(sequence: &[KeySequenceEvent]) -> Vec<INPUT> {
+    sequence
+        .iter()
+        .filter_map(|event| {
+            let vk = event.vk.resolve()?;
+            let mut flags = extended_flag(vk) | KEYBD_EVENT_FLAGS(event.flags);
+            if !event.down {
+                flags |= KEYEVENTF_KEYUP;
+            }
+            Some(keyboard_input(vk, flags))
+        })
+        .collect()
+}
+
+/// Builds the input sequence for pressing `key` while holding `modifiers`.
+///
+/// Every modifier is pressed before `key`, and they are only released after `key` has been, in
+/// reverse order.
+pub fn build_input_sequence(modifiers: &[VIRTUAL_KEY], key: VIRTUAL_KEY) -> Vec<INPUT> {
+    let keys: Vec<VIRTUAL_KEY> = modifiers.iter().copied().chain(Some(key)).collect();
+    let down = keys.iter().map(|&vk| keyboard_input(vk, extended_flag(vk)));
+    let up = keys
+        .iter()
+        .rev()
+        .map(|&vk| keyboard_input(vk, extended_flag(vk) | KEYEVENTF_KEYUP));
+
+    down.chain(up).collect()
+}
+
+/// Builds the input sequence that types `text`.
+///
+/// Every UTF-16 code unit is sent as a key press and release with `KEYEVENTF_UNICODE`, which
+/// Windows turns into `VK_PACKET` keystrokes, so characters outside the Basic Multilingual Plane
+/// are sent as their two surrogates.
+pub fn build_type_text_inputs(text: &str) -> Vec<INPUT> {
+    text.encode_utf16()
+        .flat_map(|unit| {
+            [KEYEVENTF_UNICODE, KEYEVENTF_UNICODE | KEYEVENTF_KEYUP].map(|flags| INPUT {
+                r#type: INPUT_KEYBOARD,
+                Anonymous: INPUT_0 {
+                    ki: KEYBDINPUT {
+                        // Has to be 0 with `KEYEVENTF_UNICODE`
+                        wVk: VIRTUAL_KEY(0),
+                        wScan: unit,
+                        dwFlags: flags,
+                        time: 0,
+                        dwExtraInfo: 0,
+                    },
+                },
+            })
+        })
+        .collect()
+}
+
+/// Builds the input that turns the mouse wheel by `clicks` notches, away from the user if positive.
+///
+/// Wheel input goes to the window under the cursor wherever it is when the input is sent, so the
+/// sequence doesn't depend on the cursor position.
+pub fn build_scroll_inputs(clicks: i32) -> Vec<INPUT> {
+    // `WHEEL_DELTA` is 120
+    #[allow(clippy::cast_possible_wrap)]
+    let amount = clicks.saturating_mul(WHEEL_DELTA as i32);
+    vec![INPUT {
+        r#type: INPUT_MOUSE,
+        Anonymous: INPUT_0 {
+            mi: MOUSEINPUT {
+                // Negative amounts are passed as their two's complement
+                #[allow(clippy::cast_sign_loss)]
+                mouseData: amount as u32,
+                dwFlags: MOUSEEVENTF_WHEEL,
+                dwExtraInfo: HOTCORNERS_EXTRA_INFO,
+                ..Default::default()
+            },
+        },
+    }]
+}
+
+/// Keys on the navigation cluster have to be sent as extended keys, otherwise they are read as
+/// their numpad counterparts
+fn extended_flag(vk: VIRTUAL_KEY) -> KEYBD_EVENT_FLAGS {
+    match vk {
+        VK_LEFT | VK_RIGHT | VK_UP | VK_DOWN | VK_HOME | VK_END | VK_PRIOR | VK_NEXT
+        | VK_INSERT | VK_DELETE => KEYEVENTF_EXTENDEDKEY,
+        _ => KEYBD_EVENT_FLAGS(0),
+    }
+}
+
+/// Builds the input sequence `action` sends, which is empty for actions that don't send input
+pub fn build_action_inputs(action: &ActionType) -> Vec<INPUT> {
+    match action {
+        ActionType::Keystroke(keys) => build_key_inputs(keys),
+        ActionType::Keys { sequence } => build_keystroke_inputs(sequence),
+        ActionType::ShowDesktop => build_input_sequence(&[VK_LWIN], VK_D),
+        ActionType::SwitchDesktopLeft => build_input_sequence(&[VK_LWIN, VK_CONTROL], VK_LEFT),
+        ActionType::SwitchDesktopRight => build_input_sequence(&[VK_LWIN, VK_CONTROL], VK_RIGHT),
+        ActionType::NewDesktop => build_input_sequence(&[VK_LWIN, VK_CONTROL], VK_D),
+        ActionType::CloseDesktop => build_input_sequence(&[VK_LWIN, VK_CONTROL], VK_F4),
+        ActionType::Copilot => build_input_sequence(&[VK_LWIN], VK_C),
+        ActionType::RunDialog => build_input_sequence(&[VK_LWIN], VK_R),
+        ActionType::Search => build_input_sequence(&[VK_LWIN], VK_S),
+        ActionType::GameBar => build_input_sequence(&[VK_LWIN], VK_G),
+        ActionType::TypeText { text } => build_type_text_inputs(text),
+        ActionType::ScrollWheel { delta } => build_scroll_inputs(*delta),
+        ActionType::Exec { .. }
+        | ActionType::LockScreen
+        | ActionType::MuteToggle
+        | ActionType::VolumeUp { .. }
+        | ActionType::VolumeDown { .. }
+        | ActionType::CycleAudioDevice { .. }
+        | ActionType::Screenshot { .. }
+        | ActionType::ToggleWindow { .. }
+        | ActionType::MoveWindowToMonitor { .. }
+        | ActionType::OpenUrl { .. }
+        | ActionType::Settings { .. }
+        | ActionType::ToggleTaskbarAutohide
+        | ActionType::Brightness { .. }
+        | ActionType::DisplayMode { .. }
+        | ActionType::ToggleHdr
+        | ActionType::RotateDisplay { .. }
+        | ActionType::SetResolution { .. }
+        | ActionType::Script { .. }
+        | ActionType::ToggleDesktopIcons
+        | ActionType::NightLight { .. }
+        | ActionType::FocusAssist
+        | ActionType::OnScreenKeyboard
+        | ActionType::MobilityCenter
+        | ActionType::CloseWindow { .. }
+        | ActionType::WindowState { .. }
+        | ActionType::Plugin { .. } => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The `wScan`, `wVk` and flags of each keyboard input, in order
+    fn keys(inputs: &[INPUT]) -> Vec<(u16, VIRTUAL_KEY, KEYBD_EVENT_FLAGS)> {
+        inputs
+            .iter()
+            .map(|input| {
+                assert_eq!(input.r#type, INPUT_KEYBOARD);
+                let ki = unsafe { input.Anonymous.ki };
+                (ki.wScan, ki.wVk, ki.dwFlags)
+            })
+            .collect()
+    }
+
+    /// The press and release of the UTF-16 code `unit` with `KEYEVENTF_UNICODE`
+    fn unicode(unit: u16) -> [(u16, VIRTUAL_KEY, KEYBD_EVENT_FLAGS); 2] {
+        [
+            (unit, VIRTUAL_KEY(0), KEYEVENTF_UNICODE),
+            (unit, VIRTUAL_KEY(0), KEYEVENTF_UNICODE | KEYEVENTF_KEYUP),
+        ]
+    }
+
+    #[test]
+    fn run_dialog_presses_win_r() {
+        let none = KEYBD_EVENT_FLAGS(0);
+        assert_eq!(
+            keys(&build_action_inputs(&ActionType::RunDialog)),
+            [
+                (0, VK_LWIN, none),
+                (0, VK_R, none),
+                (0, VK_R, KEYEVENTF_KEYUP),
+                (0, VK_LWIN, KEYEVENTF_KEYUP),
+            ]
+        );
+    }
+
+    #[test]
+    fn run_dialog_matches_the_same_key_combination() {
+        let win_r = [
+            KeyEvent {
+                vk: VK_LWIN.0,
+                flags: 0,
+            },
+            KeyEvent {
+                vk: VK_R.0,
+                flags: 0,
+            },
+        ];
+        assert_eq!(
+            keys(&build_action_inputs(&ActionType::RunDialog)),
+            keys(&build_key_inputs(&win_r))
+        );
+    }
+
+    #[test]
+    fn types_ascii_one_unit_per_character() {
+        let expected: Vec<_> = [u16::from(b'H'), u16::from(b'i')]
+            .into_iter()
+            .flat_map(unicode)
+            .collect();
+        assert_eq!(keys(&build_type_text_inputs("Hi")), expected);
+    }
+
+    #[test]
+    fn types_bmp_characters_as_themselves() {
+        // U+00E9 LATIN SMALL LETTER E WITH ACUTE and U+20AC EURO SIGN
+        let expected: Vec<_> = [0x00E9, 0x20AC].into_iter().flat_map(unicode).collect();
+        assert_eq!(keys(&build_type_text_inputs("é€")), expected);
+    }
+
+    #[test]
+    fn types_characters_outside_the_bmp_as_surrogate_pairs() {
+        // U+1F600 GRINNING FACE
+        let expected: Vec<_> = [0xD83D, 0xDE00].into_iter().flat_map(unicode).collect();
+        assert_eq!(keys(&build_type_text_inputs("😀")), expected);
+    }
+
+    #[test]
+    fn types_nothing_for_empty_text() {
+        assert!(build_type_text_inputs("").is_empty());
+    }
+}
@@ -0,0 +1,41 @@
+//! Hot corner zones, configuration and actions, shared by the hotcorners binary
+#![cfg(windows)]
+// Only the hotcorners binary uses this library, it isn't meant to be published
+#![allow(
+    clippy::missing_errors_doc,
+    clippy::missing_panics_doc,
+    clippy::must_use_candidate
+)]
+
+pub mod actions;
+pub mod activation_log;
+#[cfg(feature = "audio")]
+pub mod audio;
+#[cfg(feature = "wmi")]
+pub mod brightness;
+pub mod com;
+pub mod config;
+pub mod corners;
+pub mod display;
+#[cfg_attr(not(feature = "etw"), path = "no_etw.rs")]
+pub mod etw;
+#[cfg(feature = "focus-assist")]
+pub mod focus_assist;
+pub mod guard;
+pub mod hotkeys;
+pub mod input;
+pub mod monitors;
+pub mod night_light;
+#[cfg(feature = "plugins")]
+pub mod plugins;
+pub mod power;
+pub mod screenshot;
+#[cfg(feature = "scripting")]
+pub mod scripting;
+pub mod suppress;
+pub mod window;
+pub mod worker;
+
+pub use config::{ActionType, Config};
+pub use corners::{Corner, CornerState, HotZone};
+pub use worker::hot_corner_fn;
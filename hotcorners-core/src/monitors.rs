@@ -0,0 +1,115 @@
+//! The monitors attached to the desktop, shared by the corner zones, the window and display
+//! actions and `--diagnose`
+
+use windows::Win32::{
+    Foundation::{BOOL, LPARAM, RECT},
+    Graphics::Gdi::{EnumDisplayMonitors, GetMonitorInfoW, HDC, HMONITOR, MONITORINFOEXW},
+    UI::{
+        HiDpi::{GetDpiForMonitor, MDT_EFFECTIVE_DPI},
+        WindowsAndMessaging::{GetSystemMetrics, MONITORINFOF_PRIMARY, SM_CXSCREEN, SM_CYSCREEN},
+    },
+};
+
+use crate::corners::DEFAULT_DPI;
+
+/// A monitor attached to the desktop
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MonitorInfo {
+    /// Position in the order `EnumDisplayMonitors` lists the monitors, from 0
+    pub index: usize,
+    /// GDI device name, e.g. `\\.\DISPLAY1`
+    pub device_name: String,
+    /// Bounds in physical screen coordinates
+    pub bounds: RECT,
+    /// `bounds` without the taskbar and other app bars
+    pub work_area: RECT,
+    /// Effective DPI, `DEFAULT_DPI` at 100% scaling
+    pub dpi: u32,
+    /// Whether it shows the primary desktop, with its top-left corner at `(0, 0)`
+    pub is_primary: bool,
+}
+
+/// Describes every monitor attached to the desktop, falling back to just the primary monitor if
+/// they can't be enumerated
+pub fn enumerate_monitors() -> Vec<MonitorInfo> {
+    let mut monitors: Vec<MonitorInfo> = Vec::new();
+    let ok = unsafe {
+        EnumDisplayMonitors(
+            None,
+            None,
+            Some(enum_monitor_callback),
+            LPARAM(std::ptr::from_mut(&mut monitors) as isize),
+        )
+    };
+
+    if !ok.as_bool() || monitors.is_empty() {
+        return vec![primary_monitor()];
+    }
+    monitors
+}
+
+/// Callback for `EnumDisplayMonitors`, `data` points to the `Vec<MonitorInfo>` to fill
+extern "system" fn enum_monitor_callback(
+    monitor: HMONITOR,
+    _hdc: HDC,
+    _clip: *mut RECT,
+    data: LPARAM,
+) -> BOOL {
+    unsafe {
+        let monitors = &mut *(data.0 as *mut Vec<MonitorInfo>);
+        let mut info = MONITORINFOEXW::default();
+        // `size_of::<MONITORINFOEXW>()` will never exceed u32::MAX
+        #[allow(clippy::cast_possible_truncation)]
+        {
+            info.monitorInfo.cbSize = std::mem::size_of::<MONITORINFOEXW>() as u32;
+        }
+        if GetMonitorInfoW(monitor, (&raw mut info).cast()).as_bool() {
+            let len = info
+                .szDevice
+                .iter()
+                .position(|&c| c == 0)
+                .unwrap_or(info.szDevice.len());
+            monitors.push(MonitorInfo {
+                index: monitors.len(),
+                device_name: String::from_utf16_lossy(&info.szDevice[..len]),
+                bounds: info.monitorInfo.rcMonitor,
+                work_area: info.monitorInfo.rcWork,
+                dpi: monitor_dpi(monitor),
+                is_primary: info.monitorInfo.dwFlags & MONITORINFOF_PRIMARY != 0,
+            });
+        }
+    }
+
+    true.into()
+}
+
+/// Effective DPI of `monitor`, or `DEFAULT_DPI` if it can't be queried
+fn monitor_dpi(monitor: HMONITOR) -> u32 {
+    let (mut dpi_x, mut dpi_y) = (0, 0);
+    let result =
+        unsafe { GetDpiForMonitor(monitor, MDT_EFFECTIVE_DPI, &raw mut dpi_x, &raw mut dpi_y) };
+    match result {
+        Ok(()) if dpi_x > 0 => dpi_x,
+        _ => DEFAULT_DPI,
+    }
+}
+
+/// Describes the primary monitor using `GetSystemMetrics`
+fn primary_monitor() -> MonitorInfo {
+    let (width, height) = unsafe { (GetSystemMetrics(SM_CXSCREEN), GetSystemMetrics(SM_CYSCREEN)) };
+    let screen = RECT {
+        left: 0,
+        top: 0,
+        right: width,
+        bottom: height,
+    };
+
+    MonitorInfo {
+        index: 0,
+        device_name: String::new(),
+        bounds: screen,
+        work_area: screen,
+        dpi: DEFAULT_DPI,
+        is_primary: true,
+    }
+}
@@ -0,0 +1,188 @@
+//! Night Light, either through its Settings page or by rewriting its state in the registry.
+//!
+//! The state is an undocumented blob in the `CloudStore` key, under a key whose parent has moved
+//! between Windows versions, so the key is looked for every time instead of being hardcoded.
+
+use std::ops::Range;
+
+use tracing::debug;
+use windows::{
+    core::{w, HSTRING, PWSTR},
+    Win32::{
+        Foundation::{ERROR_NO_MORE_ITEMS, WIN32_ERROR},
+        System::Registry::{
+            RegCloseKey, RegEnumKeyExW, RegGetValueW, RegOpenKeyExW, RegSetValueExW, HKEY,
+            HKEY_CURRENT_USER, KEY_ENUMERATE_SUB_KEYS, KEY_QUERY_VALUE, KEY_SET_VALUE, REG_BINARY,
+            REG_SAM_FLAGS, RRF_RT_REG_BINARY,
+        },
+    },
+};
+
+use crate::actions::{shell_execute_uri, ActionError};
+
+/// Settings page with the Night Light switch
+const SETTINGS_URI: &str = "ms-settings:nightlight";
+/// Keys under `HKEY_CURRENT_USER` that the state key has been found in, newest Windows first
+const CLOUD_STORE_ROOTS: [&str; 2] = [
+    r"Software\Microsoft\Windows\CurrentVersion\CloudStore\Store\DefaultAccount\Current",
+    r"Software\Microsoft\Windows\CurrentVersion\CloudStore\Store\Cache\DefaultAccount",
+];
+/// Name of the state key, and the end of the name of its parent
+const STATE_KEY: &str = "windows.data.bluelightreduction.bluelightreductionstate";
+
+/// Offset of the byte that says whether Night Light is on
+const STATE_OFFSET: usize = 18;
+/// `STATE_OFFSET` byte while Night Light is on
+const STATE_ON: u8 = 0x15;
+/// `STATE_OFFSET` byte while Night Light is off
+const STATE_OFF: u8 = 0x13;
+/// Offset of the bytes that are only there while Night Light is on
+const ON_BYTES_OFFSET: usize = 22;
+/// Bytes that are only there while Night Light is on
+const ON_BYTES: [u8; 2] = [0x10, 0x00];
+/// Bytes of the blob's timestamp, which has to change for Windows to pick up the new state
+const TIMESTAMP: Range<usize> = 10..15;
+
+/// Turns Night Light on or off by rewriting its state in the registry with `use_registry`,
+/// otherwise opens its Settings page
+pub fn toggle(use_registry: bool) -> Result<(), ActionError> {
+    if !use_registry {
+        return shell_execute_uri(SETTINGS_URI);
+    }
+
+    let path = find_state_key().ok_or_else(|| ActionError::NotFound {
+        what: "Night Light state in the registry".to_owned(),
+    })?;
+    let key = Key::open(&path, KEY_QUERY_VALUE | KEY_SET_VALUE)
+        .map_err(|e| ActionError::win32(&e, &format!("open {path}")))?;
+    let mut data = key
+        .read_data()
+        .map_err(|e| ActionError::win32(&e, &format!("read {path}")))?;
+    let on = toggle_state(&mut data)?;
+    unsafe { RegSetValueExW(key.0, w!("Data"), 0, REG_BINARY, Some(&data)) }
+        .ok()
+        .map_err(|e| ActionError::win32(&e, &format!("write {path}")))?;
+    debug!(on, "Toggled Night Light");
+    Ok(())
+}
+
+/// Flips the state in `data` and bumps its timestamp, returning whether Night Light is now on
+fn toggle_state(data: &mut Vec<u8>) -> Result<bool, ActionError> {
+    let on = match data.get(STATE_OFFSET) {
+        Some(&STATE_ON) if data.len() >= ON_BYTES_OFFSET + ON_BYTES.len() => {
+            data[STATE_OFFSET] = STATE_OFF;
+            data.drain(ON_BYTES_OFFSET..ON_BYTES_OFFSET + ON_BYTES.len());
+            false
+        }
+        Some(&STATE_OFF) if data.len() >= ON_BYTES_OFFSET => {
+            data[STATE_OFFSET] = STATE_ON;
+            data.splice(ON_BYTES_OFFSET..ON_BYTES_OFFSET, ON_BYTES);
+            true
+        }
+        _ => {
+            return Err(ActionError::UnknownFormat {
+                what: "the Night Light state".to_owned(),
+            })
+        }
+    };
+    if let Some(byte) = data[TIMESTAMP].iter_mut().find(|byte| **byte != u8::MAX) {
+        *byte += 1;
+    }
+    Ok(on)
+}
+
+/// Path of the state key under `HKEY_CURRENT_USER`, if any of `CLOUD_STORE_ROOTS` has it
+fn find_state_key() -> Option<String> {
+    CLOUD_STORE_ROOTS.iter().find_map(|root| {
+        let key = Key::open(root, KEY_ENUMERATE_SUB_KEYS).ok()?;
+        // The parent is called e.g.
+        // `default$windows.data.bluelightreduction.bluelightreductionstate`
+        let parent = key.subkeys().find(|name| {
+            name.strip_suffix(STATE_KEY)
+                .is_some_and(|s| s.ends_with('$'))
+        })?;
+        Some(format!(r"{root}\{parent}\{STATE_KEY}"))
+    })
+}
+
+/// Registry key under `HKEY_CURRENT_USER`, closed when dropped
+struct Key(HKEY);
+
+impl Key {
+    /// Opens `path` with `access`
+    fn open(path: &str, access: REG_SAM_FLAGS) -> windows::core::Result<Self> {
+        let mut key = HKEY::default();
+        unsafe {
+            RegOpenKeyExW(
+                HKEY_CURRENT_USER,
+                &HSTRING::from(path),
+                0,
+                access,
+                &raw mut key,
+            )
+        }
+        .ok()?;
+        Ok(Self(key))
+    }
+
+    /// Names of the subkeys
+    fn subkeys(&self) -> impl Iterator<Item = String> + '_ {
+        (0..).map_while(|index| {
+            // Key names are at most 255 characters
+            let mut name = [0u16; 256];
+            // 256 will never exceed u32::MAX
+            #[allow(clippy::cast_possible_truncation)]
+            let mut len = name.len() as u32;
+            let result = unsafe {
+                RegEnumKeyExW(
+                    self.0,
+                    index,
+                    PWSTR(name.as_mut_ptr()),
+                    &raw mut len,
+                    None,
+                    PWSTR::null(),
+                    None,
+                    None,
+                )
+            };
+            match result {
+                ERROR_NO_MORE_ITEMS => None,
+                // Skipped, with a name that matches nothing
+                _ if result.is_err() => Some(String::new()),
+                _ => Some(String::from_utf16_lossy(&name[..len as usize])),
+            }
+        })
+    }
+
+    /// The binary `Data` value
+    fn read_data(&self) -> windows::core::Result<Vec<u8>> {
+        let mut len = 0u32;
+        // Ask for the size first
+        self.get_data(None, &mut len).ok()?;
+        let mut data = vec![0u8; len as usize];
+        self.get_data(Some(&mut data), &mut len).ok()?;
+        data.truncate(len as usize);
+        Ok(data)
+    }
+
+    /// Reads `Data` into `buffer`, or just its size in bytes into `len` if `buffer` is `None`
+    fn get_data(&self, buffer: Option<&mut [u8]>, len: &mut u32) -> WIN32_ERROR {
+        unsafe {
+            RegGetValueW(
+                self.0,
+                None,
+                w!("Data"),
+                RRF_RT_REG_BINARY,
+                None,
+                buffer.map(|buffer| buffer.as_mut_ptr().cast()),
+                Some(len),
+            )
+        }
+    }
+}
+
+impl Drop for Key {
+    fn drop(&mut self) {
+        let _ = unsafe { RegCloseKey(self.0) };
+    }
+}
@@ -0,0 +1,30 @@
+//! Stand-in for the ETW provider when built without the `etw` feature, every event is dropped
+
+use crate::corners::Corner;
+
+/// Does nothing, there is no provider
+pub fn register() {}
+
+/// Does nothing, there is no provider
+pub fn unregister() {}
+
+/// Does nothing, there is no provider
+pub fn hook_installed() {}
+
+/// Does nothing, there is no provider
+pub fn hook_removed() {}
+
+/// Does nothing, there is no provider
+pub fn corner_entered(_corner: Corner) {}
+
+/// Does nothing, there is no provider
+pub fn dwell_started(_corner: Corner) {}
+
+/// Does nothing, there is no provider
+pub fn dwell_cancelled(_corner: Corner) {}
+
+/// Does nothing, there is no provider
+pub fn action_started(_corner: Corner, _action: &str) {}
+
+/// Does nothing, there is no provider
+pub fn action_finished(_corner: Corner, _action: &str) {}
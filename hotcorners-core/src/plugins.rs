@@ -0,0 +1,227 @@
+//! `plugin` actions: action types from third-party DLLs with a C ABI, described by
+//! `include/hotcorners_plugin.h`.
+//!
+//! Plugins run in-process, so a separate thread with a timeout only keeps a hanging plugin from
+//! blocking the corner's worker, it can't stop a misbehaving one. Loaded DLLs are never freed,
+//! since a timed-out call may still be running their code.
+
+use std::{
+    ffi::{c_char, CStr},
+    path::{Path, PathBuf},
+    sync::{mpsc, Mutex},
+    thread,
+    time::Duration,
+};
+
+use tracing::{error, info, warn};
+use windows::{
+    core::{s, HSTRING},
+    Win32::{
+        Foundation::{FreeLibrary, POINT},
+        System::LibraryLoader::{GetProcAddress, LoadLibraryW},
+        UI::{
+            Input::KeyboardAndMouse::{SendInput, INPUT},
+            WindowsAndMessaging::GetCursorPos,
+        },
+    },
+};
+
+use crate::{
+    actions::ActionError,
+    config::{ActionType, Config},
+    corners::Corner,
+};
+
+/// Version of the ABI, `HOTCORNERS_PLUGIN_ABI_VERSION` in the header
+pub const ABI_VERSION: u32 = 1;
+/// How long loading a plugin and describing it may take
+const LOAD_TIMEOUT: Duration = Duration::from_secs(5);
+/// How long a plugin's action may take
+const EXECUTE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// What `hotcorners_plugin_describe` returns, `HotCornersPluginDescriptor` in the header
+#[derive(Debug)]
+#[repr(C)]
+pub struct PluginDescriptor {
+    /// `ABI_VERSION` the plugin was built against
+    pub abi_version: u32,
+    /// Name of the action type, NUL-terminated UTF-8 that stays valid while the DLL is loaded
+    pub name: *const c_char,
+}
+
+/// What `hotcorners_action_execute` is passed, `HotCornersActionContext` in the header
+#[derive(Debug)]
+#[repr(C)]
+pub struct ActionContext {
+    /// `ABI_VERSION`
+    pub abi_version: u32,
+    /// The corner that activated, numbered like the variants of `Corner`
+    pub corner: u32,
+    /// Cursor position in physical screen coordinates when the action started
+    pub cursor_x: i32,
+    /// See `cursor_x`
+    pub cursor_y: i32,
+    /// Sends `count` `INPUT`s like `SendInput`, returning how many were sent
+    pub send_input: unsafe extern "C" fn(inputs: *const INPUT, count: u32) -> u32,
+}
+
+/// `hotcorners_plugin_describe`
+type DescribeFn = unsafe extern "C" fn() -> PluginDescriptor;
+/// `hotcorners_action_execute`, returning 0 on success
+type ExecuteFn = unsafe extern "C" fn(ctx: *const ActionContext) -> i32;
+
+/// A loaded plugin
+struct Plugin {
+    /// DLL it was loaded from
+    path: PathBuf,
+    /// Name of its action type
+    name: String,
+    /// Its `hotcorners_action_execute`
+    execute: ExecuteFn,
+}
+
+/// Every plugin loaded so far
+static PLUGINS: Mutex<Vec<Plugin>> = Mutex::new(Vec::new());
+
+/// Loads the plugins in `config.plugin_paths` that aren't loaded yet, and warns about `plugin`
+/// actions no loaded plugin provides
+pub fn load(config: &Config) {
+    let mut plugins = PLUGINS.lock().unwrap();
+    for path in &config.plugin_paths {
+        if plugins.iter().any(|plugin| plugin.path == *path) {
+            continue;
+        }
+        match load_plugin(path) {
+            Ok(plugin) => {
+                info!(path = %path.display(), "Loaded the {} plugin", plugin.name);
+                plugins.push(plugin);
+            }
+            Err(e) => error!("Failed to load the plugin {}: {e}", path.display()),
+        }
+    }
+
+    for corner in config.all_corners() {
+        if let ActionType::Plugin { name } = &corner.action {
+            if !plugins.iter().any(|plugin| plugin.name == *name) {
+                warn!(
+                    "No plugin provides the {name} action of the {} corner, it does nothing",
+                    corner.position
+                );
+            }
+        }
+    }
+}
+
+/// Runs the action of the plugin called `name` for `corner`
+pub fn execute(name: &str, corner: Corner) -> Result<(), ActionError> {
+    let execute = PLUGINS
+        .lock()
+        .unwrap()
+        .iter()
+        .find(|plugin| plugin.name == name)
+        .map(|plugin| plugin.execute)
+        .ok_or_else(|| ActionError::NotFound {
+            what: format!("plugin providing the {name} action"),
+        })?;
+
+    let mut cursor = POINT::default();
+    let _ = unsafe { GetCursorPos(&raw mut cursor) };
+    let ctx = ActionContext {
+        abi_version: ABI_VERSION,
+        corner: corner as u32,
+        cursor_x: cursor.x,
+        cursor_y: cursor.y,
+        send_input,
+    };
+    let code = with_timeout(
+        EXECUTE_TIMEOUT,
+        format!("The {name} plugin"),
+        move || unsafe { execute(&raw const ctx) },
+    )?;
+    if code != 0 {
+        return Err(ActionError::PluginFailed {
+            name: name.to_owned(),
+            code,
+        });
+    }
+    Ok(())
+}
+
+/// Loads the DLL at `path` and asks it for its action type
+fn load_plugin(path: &Path) -> Result<Plugin, String> {
+    let dll = path.to_owned();
+    let (name, execute) = with_timeout(LOAD_TIMEOUT, "The plugin".to_owned(), move || unsafe {
+        let module = LoadLibraryW(&HSTRING::from(dll.as_path())).map_err(|e| e.to_string())?;
+        let exports = GetProcAddress(module, s!("hotcorners_plugin_describe"))
+            .zip(GetProcAddress(module, s!("hotcorners_action_execute")));
+        let Some((describe, execute)) = exports else {
+            let _ = FreeLibrary(module);
+            return Err(
+                "it doesn't export hotcorners_plugin_describe and hotcorners_action_execute"
+                    .to_owned(),
+            );
+        };
+
+        // The exports are declared with these signatures in the header
+        let describe =
+            std::mem::transmute::<unsafe extern "system" fn() -> isize, DescribeFn>(describe);
+        let execute =
+            std::mem::transmute::<unsafe extern "system" fn() -> isize, ExecuteFn>(execute);
+        let descriptor = describe();
+        if descriptor.abi_version != ABI_VERSION {
+            let _ = FreeLibrary(module);
+            return Err(format!(
+                "it was built for version {} of the plugin ABI, expected {ABI_VERSION}",
+                descriptor.abi_version
+            ));
+        }
+        if descriptor.name.is_null() {
+            let _ = FreeLibrary(module);
+            return Err("its descriptor has no name".to_owned());
+        }
+        let name = CStr::from_ptr(descriptor.name)
+            .to_str()
+            .map_err(|_| "its name isn't UTF-8".to_owned())?
+            .to_owned();
+        Ok((name, execute))
+    })
+    .map_err(|e| e.to_string())??;
+
+    Ok(Plugin {
+        path: path.to_owned(),
+        name,
+        execute,
+    })
+}
+
+/// Runs `f` on its own thread, giving up on it after `timeout`. `what` names `f` in the error.
+fn with_timeout<T: Send + 'static>(
+    timeout: Duration,
+    what: String,
+    f: impl FnOnce() -> T + Send + 'static,
+) -> Result<T, ActionError> {
+    let (sender, receiver) = mpsc::channel();
+    thread::Builder::new()
+        .name("plugin".to_owned())
+        .spawn(move || {
+            let _ = sender.send(f());
+        })
+        .map_err(|error| ActionError::Io {
+            context: "start the plugin thread".to_owned(),
+            error,
+        })?;
+    receiver
+        .recv_timeout(timeout)
+        .map_err(|_| ActionError::TimedOut { what, timeout })
+}
+
+/// `ActionContext::send_input`
+unsafe extern "C" fn send_input(inputs: *const INPUT, count: u32) -> u32 {
+    if inputs.is_null() || count == 0 {
+        return 0;
+    }
+    let inputs = std::slice::from_raw_parts(inputs, count as usize);
+    // `size_of::<INPUT>()` will never > i32::MAX
+    #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+    SendInput(inputs, std::mem::size_of::<INPUT>() as i32)
+}
@@ -0,0 +1,38 @@
+//! Tracks whether the system is running on battery, for `disable_on_battery`
+
+use std::{
+    sync::atomic::{AtomicBool, Ordering},
+    thread,
+    time::Duration,
+};
+
+use windows::Win32::System::Power::{GetSystemPowerStatus, SYSTEM_POWER_STATUS};
+
+/// How often the power status is polled, `WM_POWERBROADCAST` updates it in between
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Set while the system runs on battery
+static ON_BATTERY: AtomicBool = AtomicBool::new(false);
+
+/// Whether the system runs on battery, as of the last update
+pub fn on_battery() -> bool {
+    ON_BATTERY.load(Ordering::Relaxed)
+}
+
+/// Updates the power status right away
+pub fn update() {
+    let mut status = SYSTEM_POWER_STATUS::default();
+    if unsafe { GetSystemPowerStatus(&raw mut status) }.is_ok() {
+        // 0 is offline, 1 online and 255 unknown
+        ON_BATTERY.store(status.ACLineStatus == 0, Ordering::Relaxed);
+    }
+}
+
+/// Starts a background thread that keeps the power status up to date
+pub fn watch() {
+    update();
+    thread::spawn(|| loop {
+        thread::sleep(POLL_INTERVAL);
+        update();
+    });
+}
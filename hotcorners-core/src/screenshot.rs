@@ -0,0 +1,216 @@
+//! Screen capture for the screenshot action
+
+use std::{
+    env, fs,
+    os::windows::ffi::OsStrExt,
+    path::{Path, PathBuf},
+};
+
+use tracing::error;
+use windows::{
+    core::{Result, PCWSTR},
+    Win32::{
+        Foundation::{GENERIC_WRITE, HANDLE, HWND},
+        Graphics::{
+            Gdi::{
+                BitBlt, CreateCompatibleBitmap, CreateCompatibleDC, DeleteDC, DeleteObject, GetDC,
+                GetDIBits, ReleaseDC, SelectObject, BITMAPINFO, BITMAPINFOHEADER, BI_RGB,
+                CAPTUREBLT, DIB_RGB_COLORS, HBITMAP, HDC, SRCCOPY,
+            },
+            Imaging::{
+                CLSID_WICImagingFactory, GUID_ContainerFormatPng, GUID_WICPixelFormat32bppBGRA,
+                IWICImagingFactory, WICBitmapEncoderNoCache,
+            },
+        },
+        System::{
+            Com::{CoCreateInstance, CLSCTX_INPROC_SERVER},
+            DataExchange::{CloseClipboard, EmptyClipboard, OpenClipboard, SetClipboardData},
+            Ole::CF_BITMAP,
+            SystemInformation::GetLocalTime,
+        },
+        UI::WindowsAndMessaging::{
+            GetSystemMetrics, SM_CXVIRTUALSCREEN, SM_CYVIRTUALSCREEN, SM_XVIRTUALSCREEN,
+            SM_YVIRTUALSCREEN,
+        },
+    },
+};
+
+use crate::actions::ActionError;
+
+/// A captured screen as top-down 32-bit BGRA pixels
+struct Capture {
+    /// Width in pixels
+    width: u32,
+    /// Height in pixels
+    height: u32,
+    /// `width * height` pixels, 4 bytes each
+    pixels: Vec<u8>,
+}
+
+/// Captures every monitor and saves the result as a PNG in `dir`, or in
+/// `%USERPROFILE%\Pictures\Screenshots` if `dir` is `None`. The directory is created if it
+/// doesn't exist. If `clipboard` is set, the capture is copied to the clipboard as well.
+///
+/// Returns the path of the saved file. COM has to be initialized on the calling thread.
+pub fn take_screenshot(
+    dir: Option<&Path>,
+    clipboard: bool,
+) -> std::result::Result<PathBuf, ActionError> {
+    let dir = match dir {
+        Some(dir) => dir.to_path_buf(),
+        None => default_dir().ok_or_else(|| ActionError::NotFound {
+            what: "%USERPROFILE% variable".to_owned(),
+        })?,
+    };
+    fs::create_dir_all(&dir).map_err(|error| ActionError::Io {
+        context: format!("create {}", dir.display()),
+        error,
+    })?;
+    let path = dir.join(file_name());
+
+    let capture = unsafe { capture_screen(clipboard) }
+        .map_err(|e| ActionError::win32(&e, "capture the screen"))?;
+    unsafe { save_png(&capture, &path) }
+        .map_err(|e| ActionError::com(&e, &format!("save {}", path.display())))?;
+
+    Ok(path)
+}
+
+/// `%USERPROFILE%\Pictures\Screenshots`, where Windows' own screenshots go
+fn default_dir() -> Option<PathBuf> {
+    let profile = env::var_os("USERPROFILE")?;
+    Some(PathBuf::from(profile).join("Pictures").join("Screenshots"))
+}
+
+/// File name with the current local time, e.g. `Screenshot 2024-05-01 173205.png`
+fn file_name() -> String {
+    let now = unsafe { GetLocalTime() };
+    format!(
+        "Screenshot {:04}-{:02}-{:02} {:02}{:02}{:02}.png",
+        now.wYear, now.wMonth, now.wDay, now.wHour, now.wMinute, now.wSecond
+    )
+}
+
+/// Copies the whole virtual screen into a bitmap and reads its pixels, handing the bitmap to the
+/// clipboard if `clipboard` is set
+unsafe fn capture_screen(clipboard: bool) -> Result<Capture> {
+    let x = GetSystemMetrics(SM_XVIRTUALSCREEN);
+    let y = GetSystemMetrics(SM_YVIRTUALSCREEN);
+    let width = GetSystemMetrics(SM_CXVIRTUALSCREEN);
+    let height = GetSystemMetrics(SM_CYVIRTUALSCREEN);
+
+    let screen = GetDC(None);
+    let memory = CreateCompatibleDC(screen);
+    let bitmap = CreateCompatibleBitmap(screen, width, height);
+    let previous = SelectObject(memory, bitmap);
+
+    let result = BitBlt(
+        memory,
+        0,
+        0,
+        width,
+        height,
+        screen,
+        x,
+        y,
+        SRCCOPY | CAPTUREBLT,
+    )
+    .and_then(|()| read_pixels(memory, bitmap, width, height));
+
+    SelectObject(memory, previous);
+    let _ = DeleteDC(memory);
+    ReleaseDC(None, screen);
+
+    // The clipboard takes ownership of the bitmap, otherwise we have to free it
+    if !(clipboard && result.is_ok() && copy_to_clipboard(bitmap)) {
+        let _ = DeleteObject(bitmap);
+    }
+    result
+}
+
+/// Reads the pixels of `bitmap` as top-down 32-bit BGRA
+unsafe fn read_pixels(memory: HDC, bitmap: HBITMAP, width: i32, height: i32) -> Result<Capture> {
+    let mut info = BITMAPINFO {
+        bmiHeader: BITMAPINFOHEADER {
+            // `size_of::<BITMAPINFOHEADER>()` will never exceed u32::MAX
+            #[allow(clippy::cast_possible_truncation)]
+            biSize: std::mem::size_of::<BITMAPINFOHEADER>() as u32,
+            biWidth: width,
+            // Negative for top-down rows, which is what WIC expects
+            biHeight: -height,
+            biPlanes: 1,
+            biBitCount: 32,
+            biCompression: BI_RGB.0,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    let width = u32::try_from(width).unwrap_or(0);
+    let height = u32::try_from(height).unwrap_or(0);
+    let mut pixels = vec![0u8; width as usize * height as usize * 4];
+    let lines = GetDIBits(
+        memory,
+        bitmap,
+        0,
+        height,
+        Some(pixels.as_mut_ptr().cast()),
+        &raw mut info,
+        DIB_RGB_COLORS,
+    );
+    if lines == 0 {
+        return Err(windows::core::Error::from_win32());
+    }
+
+    // GDI leaves the alpha channel at 0, which would make the PNG fully transparent
+    for pixel in pixels.chunks_exact_mut(4) {
+        pixel[3] = 0xFF;
+    }
+
+    Ok(Capture {
+        width,
+        height,
+        pixels,
+    })
+}
+
+/// Puts `bitmap` on the clipboard, returning whether the clipboard took ownership of it
+unsafe fn copy_to_clipboard(bitmap: HBITMAP) -> bool {
+    if OpenClipboard(HWND::default()).is_err() {
+        error!("Failed to open the clipboard");
+        return false;
+    }
+
+    let copied = EmptyClipboard().is_ok()
+        && SetClipboardData(u32::from(CF_BITMAP.0), HANDLE(bitmap.0)).is_ok();
+    let _ = CloseClipboard();
+    if !copied {
+        error!("Failed to copy the screenshot to the clipboard");
+    }
+    copied
+}
+
+/// Encodes `capture` as a PNG file at `path` using the Windows Imaging Component
+unsafe fn save_png(capture: &Capture, path: &Path) -> Result<()> {
+    let factory: IWICImagingFactory =
+        CoCreateInstance(&CLSID_WICImagingFactory, None, CLSCTX_INPROC_SERVER)?;
+
+    let wide: Vec<u16> = path.as_os_str().encode_wide().chain(Some(0)).collect();
+    let stream = factory.CreateStream()?;
+    stream.InitializeFromFilename(PCWSTR(wide.as_ptr()), GENERIC_WRITE.0)?;
+
+    let encoder = factory.CreateEncoder(&GUID_ContainerFormatPng, std::ptr::null())?;
+    encoder.Initialize(&stream, WICBitmapEncoderNoCache)?;
+
+    let mut frame = None;
+    encoder.CreateNewFrame(&raw mut frame, std::ptr::null_mut())?;
+    let frame = frame.ok_or_else(windows::core::Error::empty)?;
+    frame.Initialize(None)?;
+    frame.SetSize(capture.width, capture.height)?;
+    let mut format = GUID_WICPixelFormat32bppBGRA;
+    frame.SetPixelFormat(&raw mut format)?;
+    frame.WritePixels(capture.height, capture.width * 4, &capture.pixels)?;
+    frame.Commit()?;
+
+    encoder.Commit()
+}
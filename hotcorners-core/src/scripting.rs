@@ -0,0 +1,148 @@
+//! `rhai` actions: scripts in the embedded [Rhai](https://rhai.rs) language.
+//!
+//! Scripts can only reach the system through the `hotcorners` module, and are stopped after
+//! `MAX_OPERATIONS` operations so a runaway loop can't hang the corner's worker.
+
+use std::{fs, path::Path, sync::LazyLock, thread, time::Duration};
+
+use rhai::{Array, Dynamic, Engine, EvalAltResult, Map, Module, AST};
+use windows::Win32::{
+    Foundation::POINT,
+    UI::{
+        Input::KeyboardAndMouse::{SendInput, INPUT, VIRTUAL_KEY},
+        WindowsAndMessaging::GetCursorPos,
+    },
+};
+
+use crate::{actions, input};
+
+/// Operations a script may run before it is stopped
+const MAX_OPERATIONS: u64 = 100_000;
+/// Maximum nesting of expressions at the top level of a script
+const MAX_EXPR_DEPTH: usize = 64;
+/// Maximum nesting of expressions inside functions
+const MAX_FUNCTION_EXPR_DEPTH: usize = 32;
+/// Longest wait `sleep_ms` accepts, in milliseconds. The operation limit doesn't count time spent
+/// sleeping, so this keeps a script from blocking the corner's worker for long.
+const MAX_SLEEP_MS: u64 = 5_000;
+
+/// Engine shared by every script, set up on first use
+static ENGINE: LazyLock<Engine> = LazyLock::new(|| {
+    let mut engine = Engine::new();
+    engine.set_max_operations(MAX_OPERATIONS);
+    engine.set_max_expr_depths(MAX_EXPR_DEPTH, MAX_FUNCTION_EXPR_DEPTH);
+    engine.register_static_module("hotcorners", hotcorners_module().into());
+    engine
+});
+
+/// Result of the functions scripts call
+type ScriptResult<T> = Result<T, Box<EvalAltResult>>;
+
+/// The functions scripts can call, as `hotcorners::name(...)`
+fn hotcorners_module() -> Module {
+    let mut module = Module::new();
+    module.set_native_fn("send_keys", send_keys);
+    module.set_native_fn("run_command", run_command);
+    module.set_native_fn("get_cursor_pos", get_cursor_pos);
+    module.set_native_fn("sleep_ms", sleep_ms);
+    module
+}
+
+/// Parses `script`, so syntax errors are found when the configuration loads
+pub fn compile(script: &str) -> Result<AST, String> {
+    ENGINE.compile(script).map_err(|e| e.to_string())
+}
+
+/// Parses the script in the file at `path`
+pub fn compile_file(path: &Path) -> Result<AST, String> {
+    compile(&fs::read_to_string(path).map_err(|e| e.to_string())?)
+}
+
+/// Runs `script`
+pub fn run(script: &str) -> Result<(), String> {
+    ENGINE.run_ast(&compile(script)?).map_err(|e| e.to_string())
+}
+
+/// Runs the script in the file at `path`, read again every time so edits apply right away
+pub fn run_file(path: &Path) -> Result<(), String> {
+    ENGINE
+        .run_ast(&compile_file(path)?)
+        .map_err(|e| e.to_string())
+}
+
+/// `send_keys([0x5B, 0x09])`: presses the keys with these virtual key codes in order and releases
+/// them in reverse, like a `keystroke` action
+fn send_keys(keys: Array) -> ScriptResult<()> {
+    let keys = keys
+        .into_iter()
+        .map(|key| {
+            key.as_int()
+                .ok()
+                .and_then(|vk| u16::try_from(vk).ok())
+                .map(VIRTUAL_KEY)
+                .ok_or_else(|| "send_keys expects virtual key codes from 0 to 65535".into())
+        })
+        .collect::<ScriptResult<Vec<_>>>()?;
+    let Some((&key, modifiers)) = keys.split_last() else {
+        return Ok(());
+    };
+
+    let inputs = input::build_input_sequence(modifiers, key);
+    // `size_of::<INPUT>()` will never > i32::MAX
+    #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+    let sent = unsafe { SendInput(&inputs, std::mem::size_of::<INPUT>() as i32) };
+    // it would be absurd if the size of `inputs` exceeded `u32::MAX`
+    #[allow(clippy::cast_possible_truncation)]
+    if sent != inputs.len() as u32 {
+        return Err(format!("Sent {sent} of {} key events", inputs.len()).into());
+    }
+    Ok(())
+}
+
+/// `run_command("notepad.exe", ["notes.txt"])`: launches a program, like an `exec` action
+// Rhai passes arguments by value
+#[allow(clippy::needless_pass_by_value)]
+fn run_command(path: &str, args: Array) -> ScriptResult<()> {
+    let args: Vec<String> = args.into_iter().map(|arg| arg.to_string()).collect();
+    actions::spawn_detached(Path::new(path), &args)
+        .map_err(|e| format!("Failed to launch {path}: {e}").into())
+}
+
+/// `get_cursor_pos()`: the cursor position in screen coordinates, as `#{x: ..., y: ...}`
+fn get_cursor_pos() -> ScriptResult<Map> {
+    let mut pos = POINT::default();
+    unsafe { GetCursorPos(&raw mut pos) }.map_err(|e| e.to_string())?;
+    let mut map = Map::new();
+    map.insert("x".into(), Dynamic::from_int(pos.x.into()));
+    map.insert("y".into(), Dynamic::from_int(pos.y.into()));
+    Ok(map)
+}
+
+/// `sleep_ms(500)`: waits this many milliseconds, at most `MAX_SLEEP_MS`
+fn sleep_ms(ms: i64) -> ScriptResult<()> {
+    let ms = u64::try_from(ms).map_err(|_| "sleep_ms expects a positive number")?;
+    if ms > MAX_SLEEP_MS {
+        return Err(format!("sleep_ms waits at most {MAX_SLEEP_MS} milliseconds, got {ms}").into());
+    }
+    thread::sleep(Duration::from_millis(ms));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sleep_ms_rejects_waits_past_the_maximum() {
+        let too_long = i64::try_from(MAX_SLEEP_MS + 1).unwrap();
+        assert!(sleep_ms(too_long).is_err());
+        assert!(sleep_ms(i64::MAX).is_err());
+        assert!(sleep_ms(-1).is_err());
+    }
+
+    #[test]
+    fn scripts_fail_on_a_wait_past_the_maximum() {
+        let error = run("hotcorners::sleep_ms(3600000);").unwrap_err();
+        assert!(error.contains("at most 5000 milliseconds"), "{error}");
+    }
+}
@@ -0,0 +1,173 @@
+//! Checks for situations in which the corners shouldn't activate
+
+use std::{ffi::OsString, os::windows::ffi::OsStringExt, path::PathBuf, sync::Mutex};
+
+use windows::{
+    core::PWSTR,
+    Win32::{
+        Foundation::{CloseHandle, HWND, MAX_PATH, RECT},
+        Graphics::Gdi::{
+            GetMonitorInfoW, MonitorFromWindow, MONITORINFO, MONITOR_DEFAULTTONEAREST,
+        },
+        System::{
+            SystemInformation::GetTickCount64,
+            Threading::{
+                OpenProcess, QueryFullProcessImageNameW, PROCESS_NAME_WIN32,
+                PROCESS_QUERY_LIMITED_INFORMATION,
+            },
+        },
+        UI::WindowsAndMessaging::{
+            GetClassNameW, GetForegroundWindow, GetShellWindow, GetWindowRect,
+            GetWindowThreadProcessId,
+        },
+    },
+};
+
+/// How many pixels a window may fall short of the monitor bounds and still count as fullscreen.
+/// Some borderless fullscreen windows are sized one pixel off.
+const FULLSCREEN_TOLERANCE: i32 = 1;
+
+/// How long the details of the foreground window are reused while it stays in the foreground, in
+/// milliseconds. Looking up the process is too expensive for every mouse event.
+const FOREGROUND_CACHE_MS: u64 = 200;
+
+/// Details of the foreground window from the last time they were looked up
+static FOREGROUND: Mutex<Option<Foreground>> = Mutex::new(None);
+
+/// Cached details of a foreground window
+#[derive(Debug)]
+struct Foreground {
+    /// The window, as an `isize` since `HWND` isn't `Send`
+    hwnd: isize,
+    /// `GetTickCount64` value of when the details were looked up
+    checked_at: u64,
+    /// Window class name, e.g. `UnityWndClass`. `None` until it is needed.
+    class: Option<String>,
+    /// File name of the window's process, e.g. `dota2.exe`. `None` until it is needed, and empty
+    /// if it couldn't be looked up.
+    process: Option<String>,
+}
+
+/// Checks whether the foreground window covers its entire monitor, e.g. a game or a video player.
+///
+/// Compares against the full monitor bounds rather than the work area, so maximized windows
+/// don't count.
+pub fn foreground_is_fullscreen() -> bool {
+    unsafe {
+        let hwnd = GetForegroundWindow();
+        if hwnd.is_invalid() || is_desktop(hwnd) {
+            return false;
+        }
+
+        let mut window = RECT::default();
+        if GetWindowRect(hwnd, &raw mut window).is_err() {
+            return false;
+        }
+
+        let mut info = MONITORINFO {
+            // `size_of::<MONITORINFO>()` will never exceed u32::MAX
+            #[allow(clippy::cast_possible_truncation)]
+            cbSize: std::mem::size_of::<MONITORINFO>() as u32,
+            ..Default::default()
+        };
+        let monitor = MonitorFromWindow(hwnd, MONITOR_DEFAULTTONEAREST);
+        if !GetMonitorInfoW(monitor, &raw mut info).as_bool() {
+            return false;
+        }
+
+        covers(&window, &info.rcMonitor)
+    }
+}
+
+/// Whether `window` covers all of `monitor`, give or take `FULLSCREEN_TOLERANCE`
+fn covers(window: &RECT, monitor: &RECT) -> bool {
+    window.left <= monitor.left + FULLSCREEN_TOLERANCE
+        && window.top <= monitor.top + FULLSCREEN_TOLERANCE
+        && window.right >= monitor.right - FULLSCREEN_TOLERANCE
+        && window.bottom >= monitor.bottom - FULLSCREEN_TOLERANCE
+}
+
+/// The desktop covers the whole monitor too, but clicking it shouldn't disable the corners
+fn is_desktop(hwnd: HWND) -> bool {
+    hwnd == unsafe { GetShellWindow() } || class_name(hwnd) == "WorkerW"
+}
+
+/// Checks whether the foreground window's class is in `classes` or its process' file name is in
+/// `processes`, ignoring case.
+///
+/// The class is checked first, since it is much cheaper to look up than the process.
+pub fn foreground_suppressed(classes: &[String], processes: &[String]) -> bool {
+    if classes.is_empty() && processes.is_empty() {
+        return false;
+    }
+
+    let (hwnd, now) = unsafe { (GetForegroundWindow(), GetTickCount64()) };
+    let mut cache = FOREGROUND.lock().unwrap();
+    let foreground = match cache.take() {
+        Some(cached)
+            if cached.hwnd == hwnd.0 as isize
+                && now.saturating_sub(cached.checked_at) < FOREGROUND_CACHE_MS =>
+        {
+            cache.insert(cached)
+        }
+        _ => cache.insert(Foreground {
+            hwnd: hwnd.0 as isize,
+            checked_at: now,
+            class: None,
+            process: None,
+        }),
+    };
+
+    let matches =
+        |names: &[String], value: &str| names.iter().any(|name| name.eq_ignore_ascii_case(value));
+    if !classes.is_empty() {
+        let class = foreground.class.get_or_insert_with(|| class_name(hwnd));
+        if matches(classes, class) {
+            return true;
+        }
+    }
+    if !processes.is_empty() {
+        let process = foreground
+            .process
+            .get_or_insert_with(|| process_name(hwnd).unwrap_or_default());
+        if matches(processes, process) {
+            return true;
+        }
+    }
+
+    false
+}
+
+/// Window class name of `hwnd`
+pub(crate) fn class_name(hwnd: HWND) -> String {
+    // Class names are at most 256 characters long
+    let mut class = [0u16; 256];
+    let len = usize::try_from(unsafe { GetClassNameW(hwnd, &mut class) }).unwrap_or(0);
+    String::from_utf16_lossy(&class[..len])
+}
+
+/// File name of the process that owns `hwnd`
+fn process_name(hwnd: HWND) -> Option<String> {
+    unsafe {
+        let mut pid = 0u32;
+        GetWindowThreadProcessId(hwnd, Some(&raw mut pid));
+        if pid == 0 {
+            return None;
+        }
+
+        let process = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, pid).ok()?;
+        let mut path = [0u16; MAX_PATH as usize];
+        let mut len = MAX_PATH;
+        let result = QueryFullProcessImageNameW(
+            process,
+            PROCESS_NAME_WIN32,
+            PWSTR(path.as_mut_ptr()),
+            &raw mut len,
+        );
+        let _ = CloseHandle(process);
+        result.ok()?;
+
+        let path = PathBuf::from(OsString::from_wide(&path[..len as usize]));
+        Some(path.file_name()?.to_string_lossy().into_owned())
+    }
+}
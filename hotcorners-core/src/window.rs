@@ -0,0 +1,466 @@
+//! Actions on the top-level windows of other programs and the taskbar
+
+use serde::{Deserialize, Serialize};
+use tracing::{debug, warn};
+use windows::{
+    core::{w, HSTRING},
+    Win32::{
+        Foundation::{BOOL, HWND, LPARAM, WPARAM},
+        Graphics::Gdi::{
+            GetMonitorInfoW, MonitorFromWindow, MONITORINFO, MONITOR_DEFAULTTONEAREST,
+        },
+        System::Threading::GetCurrentProcessId,
+        UI::{
+            Shell::{SHAppBarMessage, ABM_GETSTATE, ABM_SETSTATE, ABS_AUTOHIDE, APPBARDATA},
+            WindowsAndMessaging::{
+                EnumWindows, FindWindowExW, FindWindowW, GetForegroundWindow, GetWindowLongW,
+                GetWindowPlacement, GetWindowTextW, GetWindowThreadProcessId, IsIconic,
+                IsWindowVisible, IsZoomed, MessageBoxW, PostMessageW, SetForegroundWindow,
+                SetWindowPos, ShowWindow, GWL_STYLE, IDYES, MB_ICONQUESTION, MB_SETFOREGROUND,
+                MB_TOPMOST, MB_YESNO, SWP_NOACTIVATE, SWP_NOZORDER, SW_MAXIMIZE, SW_MINIMIZE,
+                SW_RESTORE, SW_SHOW, SW_SHOWMAXIMIZED, WINDOWPLACEMENT, WM_CLOSE, WM_COMMAND,
+                WS_MAXIMIZEBOX,
+            },
+        },
+    },
+};
+
+use crate::{
+    actions::{is_process_running, shell_execute_uri, ActionError},
+    monitors::enumerate_monitors,
+    suppress::class_name,
+};
+
+/// Command of the desktop's "View > Show desktop icons" menu item
+const TOGGLE_DESKTOP_ICONS: usize = 0x7402;
+
+/// Left or right, for moving windows between monitors
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum HorizontalDirection {
+    /// Towards the monitor on the left
+    Left,
+    /// Towards the monitor on the right
+    Right,
+}
+
+/// What the `window-state` action does to the foreground window
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum WindowStateAction {
+    /// Maximizes it
+    Maximize,
+    /// Minimizes it
+    Minimize,
+    /// Restores it from being maximized or minimized
+    Restore,
+    /// Maximizes it, or restores it if it's maximized
+    Toggle,
+}
+
+/// Which window a window action applies to
+#[derive(Debug, Clone, Copy)]
+pub struct FindWindowSpec<'a> {
+    /// Window class name, compared exactly, e.g. `CASCADIA_HOSTING_WINDOW_CLASS`
+    pub class: Option<&'a str>,
+    /// Part of the window title, ignoring case
+    pub title_contains: Option<&'a str>,
+}
+
+impl FindWindowSpec<'_> {
+    /// Finds the first visible top-level window that matches.
+    ///
+    /// A class on its own is looked up with `FindWindowW`, anything with a title has to look at
+    /// every top-level window.
+    pub fn find(&self) -> Option<HWND> {
+        if let (Some(class), None) = (self.class, self.title_contains) {
+            let hwnd = unsafe { FindWindowW(&HSTRING::from(class), None) }.ok()?;
+            if unsafe { IsWindowVisible(hwnd) }.as_bool() {
+                return Some(hwnd);
+            }
+        }
+
+        let mut search = Search {
+            spec: *self,
+            title: self.title_contains.map(str::to_lowercase),
+            found: None,
+        };
+        // Fails when the callback stops the enumeration early
+        let _ = unsafe {
+            EnumWindows(
+                Some(match_window),
+                LPARAM(std::ptr::from_mut(&mut search) as isize),
+            )
+        };
+        search.found
+    }
+
+    /// Whether `hwnd` matches, `title` is `title_contains` in lower case
+    fn matches(&self, hwnd: HWND, title: Option<&str>) -> bool {
+        if self.class.is_some_and(|class| class_name(hwnd) != class) {
+            return false;
+        }
+        title.is_none_or(|title| window_title(hwnd).to_lowercase().contains(title))
+    }
+}
+
+/// State of an `EnumWindows` search
+struct Search<'a> {
+    /// What to look for
+    spec: FindWindowSpec<'a>,
+    /// `spec.title_contains` in lower case
+    title: Option<String>,
+    /// The first matching window
+    found: Option<HWND>,
+}
+
+/// `EnumWindows` callback, stops at the first visible window that matches the `Search` `lparam`
+/// points to
+unsafe extern "system" fn match_window(hwnd: HWND, lparam: LPARAM) -> BOOL {
+    let search = &mut *(lparam.0 as *mut Search);
+    if IsWindowVisible(hwnd).as_bool() && search.spec.matches(hwnd, search.title.as_deref()) {
+        search.found = Some(hwnd);
+        return BOOL(0);
+    }
+    BOOL(1)
+}
+
+/// Title of `hwnd`, empty if it has none
+fn window_title(hwnd: HWND) -> String {
+    let mut title = [0u16; 512];
+    let len = usize::try_from(unsafe { GetWindowTextW(hwnd, &mut title) }).unwrap_or(0);
+    String::from_utf16_lossy(&title[..len])
+}
+
+/// Minimizes `hwnd` if it is the foreground window, otherwise restores it if it is minimized and
+/// brings it to the foreground
+pub fn toggle_window(hwnd: HWND) {
+    unsafe {
+        if GetForegroundWindow() == hwnd {
+            let _ = ShowWindow(hwnd, SW_MINIMIZE);
+            return;
+        }
+
+        // Only restore minimized windows, restoring a maximized one would unmaximize it
+        if IsIconic(hwnd).as_bool() {
+            let _ = ShowWindow(hwnd, SW_RESTORE);
+        }
+        if !SetForegroundWindow(hwnd).as_bool() {
+            warn!("Windows refused to bring the window to the foreground");
+        }
+    }
+}
+
+/// Shows the on-screen keyboard, hiding it instead if it's already showing, and starts it if it
+/// isn't running
+pub fn toggle_on_screen_keyboard() -> Result<(), ActionError> {
+    if !is_process_running("osk.exe") {
+        return shell_execute_uri("osk.exe");
+    }
+
+    let hwnd =
+        unsafe { FindWindowW(w!("OSKMainClass"), None) }.map_err(|_| ActionError::NotFound {
+            what: "on-screen keyboard window".to_owned(),
+        })?;
+    unsafe {
+        if IsWindowVisible(hwnd).as_bool() && !IsIconic(hwnd).as_bool() {
+            let _ = ShowWindow(hwnd, SW_MINIMIZE);
+            return Ok(());
+        }
+
+        let _ = ShowWindow(
+            hwnd,
+            if IsIconic(hwnd).as_bool() {
+                SW_RESTORE
+            } else {
+                SW_SHOW
+            },
+        );
+        if !SetForegroundWindow(hwnd).as_bool() {
+            warn!("Windows refused to bring the on-screen keyboard to the foreground");
+        }
+    }
+    Ok(())
+}
+
+/// Brings the Mobility Center to the foreground, starting it if it isn't open
+pub fn open_mobility_center() -> Result<(), ActionError> {
+    let Ok(hwnd) = (unsafe { FindWindowW(w!("MobilityCenter"), None) }) else {
+        return shell_execute_uri("mblctr.exe");
+    };
+    unsafe {
+        if IsIconic(hwnd).as_bool() {
+            let _ = ShowWindow(hwnd, SW_RESTORE);
+        }
+        if !SetForegroundWindow(hwnd).as_bool() {
+            warn!("Windows refused to bring the Mobility Center to the foreground");
+        }
+    }
+    Ok(())
+}
+
+/// Asks the foreground window to close, like clicking its close button. With `confirm` a message
+/// box asks first.
+///
+/// Windows of hotcorners itself, like the hidden one it receives messages with, are left alone.
+pub fn close_foreground_window(confirm: bool) -> Result<(), ActionError> {
+    let hwnd = unsafe { GetForegroundWindow() };
+    if hwnd.is_invalid() {
+        return Err(ActionError::NotFound {
+            what: "foreground window".to_owned(),
+        });
+    }
+    if !may_close(window_process(hwnd), unsafe { GetCurrentProcessId() }) {
+        debug!("The foreground window belongs to hotcorners, not closing it");
+        return Ok(());
+    }
+
+    if confirm {
+        let title = window_title(hwnd);
+        let question = if title.is_empty() {
+            "Close this window?".to_owned()
+        } else {
+            format!("Close {title}?")
+        };
+        let answer = unsafe {
+            MessageBoxW(
+                None,
+                &HSTRING::from(question),
+                w!("hotcorners"),
+                MB_YESNO | MB_ICONQUESTION | MB_SETFOREGROUND | MB_TOPMOST,
+            )
+        };
+        if answer != IDYES {
+            return Ok(());
+        }
+    }
+
+    unsafe { PostMessageW(hwnd, WM_CLOSE, WPARAM(0), LPARAM(0)) }
+        .map_err(|e| ActionError::win32(&e, "close the window"))
+}
+
+/// ID of the process that owns `hwnd`, 0 if it doesn't exist
+fn window_process(hwnd: HWND) -> u32 {
+    let mut pid = 0u32;
+    unsafe { GetWindowThreadProcessId(hwnd, Some(&raw mut pid)) };
+    pid
+}
+
+/// Whether the close-window action may close a window of the process `owner`, where `current` is
+/// this process. hotcorners' own windows, like its hidden message window and the confirmation
+/// dialog, never get closed.
+fn may_close(owner: u32, current: u32) -> bool {
+    owner != current
+}
+
+/// Maximizes, minimizes or restores the foreground window.
+///
+/// Windows that can't be maximized, like most dialogs, are left as they are.
+pub fn set_foreground_window_state(state: WindowStateAction) -> windows::core::Result<()> {
+    let hwnd = unsafe { GetForegroundWindow() };
+    let mut placement = WINDOWPLACEMENT {
+        // `size_of::<WINDOWPLACEMENT>()` will never exceed u32::MAX
+        #[allow(clippy::cast_possible_truncation)]
+        length: std::mem::size_of::<WINDOWPLACEMENT>() as u32,
+        ..Default::default()
+    };
+    unsafe { GetWindowPlacement(hwnd, &raw mut placement) }?;
+    // `SW_SHOWMAXIMIZED` is 3
+    #[allow(clippy::cast_sign_loss)]
+    let maximized = placement.showCmd == SW_SHOWMAXIMIZED.0 as u32;
+
+    let command = match state {
+        WindowStateAction::Restore => SW_RESTORE,
+        WindowStateAction::Toggle if maximized => SW_RESTORE,
+        WindowStateAction::Maximize | WindowStateAction::Toggle => SW_MAXIMIZE,
+        WindowStateAction::Minimize => SW_MINIMIZE,
+    };
+    // The style bits are the same whatever the sign
+    #[allow(clippy::cast_sign_loss)]
+    let style = unsafe { GetWindowLongW(hwnd, GWL_STYLE) } as u32;
+    if command == SW_MAXIMIZE && style & WS_MAXIMIZEBOX.0 == 0 {
+        warn!("The {} window can't be maximized", class_name(hwnd));
+        return Ok(());
+    }
+
+    let _ = unsafe { ShowWindow(hwnd, command) };
+    debug!(?state, maximized, "Changed the foreground window's state");
+    Ok(())
+}
+
+/// Moves the foreground window to the monitor next to its current one in `direction` and resizes
+/// it to fill that monitor's work area.
+///
+/// Monitors are ordered by their left edge. Past the last monitor in `direction` the window moves
+/// to the first one on the other side if `wrap` is set, and stays where it is otherwise.
+pub fn move_to_adjacent_monitor(
+    direction: HorizontalDirection,
+    wrap: bool,
+) -> windows::core::Result<()> {
+    let hwnd = unsafe { GetForegroundWindow() };
+    if hwnd.is_invalid() {
+        return Ok(());
+    }
+
+    let mut current = MONITORINFO {
+        // `size_of::<MONITORINFO>()` will never exceed u32::MAX
+        #[allow(clippy::cast_possible_truncation)]
+        cbSize: std::mem::size_of::<MONITORINFO>() as u32,
+        ..Default::default()
+    };
+    let monitor = unsafe { MonitorFromWindow(hwnd, MONITOR_DEFAULTTONEAREST) };
+    if !unsafe { GetMonitorInfoW(monitor, &raw mut current) }.as_bool() {
+        return Ok(());
+    }
+
+    let mut monitors = enumerate_monitors();
+    monitors.sort_by_key(|monitor| monitor.bounds.left);
+    let Some(index) = monitors
+        .iter()
+        .position(|monitor| monitor.bounds == current.rcMonitor)
+    else {
+        return Ok(());
+    };
+    let target = match direction {
+        HorizontalDirection::Left if index > 0 => index - 1,
+        HorizontalDirection::Left if wrap => monitors.len() - 1,
+        HorizontalDirection::Right if index + 1 < monitors.len() => index + 1,
+        HorizontalDirection::Right if wrap => 0,
+        _ => {
+            debug!(?direction, "No monitor in that direction");
+            return Ok(());
+        }
+    };
+    if target == index {
+        return Ok(());
+    }
+
+    let work = monitors[target].work_area;
+    unsafe {
+        // A maximized window would keep its maximized size and position on the old monitor
+        if IsZoomed(hwnd).as_bool() || IsIconic(hwnd).as_bool() {
+            let _ = ShowWindow(hwnd, SW_RESTORE);
+        }
+        SetWindowPos(
+            hwnd,
+            None,
+            work.left,
+            work.top,
+            work.right - work.left,
+            work.bottom - work.top,
+            SWP_NOZORDER | SWP_NOACTIVATE,
+        )
+    }
+}
+
+/// Shows or hides the desktop icons, like "View > Show desktop icons" in the desktop's context
+/// menu
+pub fn toggle_desktop_icons() -> windows::core::Result<()> {
+    let view = desktop_view()?;
+    unsafe { PostMessageW(view, WM_COMMAND, WPARAM(TOGGLE_DESKTOP_ICONS), LPARAM(0)) }?;
+    debug!("Toggled desktop icons");
+    Ok(())
+}
+
+/// The `SHELLDLL_DefView` window that shows the desktop icons.
+///
+/// It's a child of `Progman`, unless a wallpaper slideshow or `Win+Tab` has moved it into one of
+/// the `WorkerW` windows behind the desktop.
+fn desktop_view() -> windows::core::Result<HWND> {
+    let progman = unsafe { FindWindowW(w!("Progman"), None) }?;
+    let view = unsafe { FindWindowExW(progman, HWND::default(), w!("SHELLDLL_DefView"), None) };
+    if view.is_ok() {
+        return view;
+    }
+
+    let mut worker = HWND::default();
+    loop {
+        worker = unsafe { FindWindowExW(HWND::default(), worker, w!("WorkerW"), None) }?;
+        if let Ok(view) =
+            unsafe { FindWindowExW(worker, HWND::default(), w!("SHELLDLL_DefView"), None) }
+        {
+            return Ok(view);
+        }
+    }
+}
+
+/// Turns auto-hiding of the taskbar on or off, whichever it isn't.
+///
+/// The setting is shared by the taskbars on every monitor, so only the primary taskbar is asked
+/// for it.
+pub fn toggle_taskbar_autohide() -> windows::core::Result<()> {
+    let taskbar = unsafe { FindWindowW(w!("Shell_TrayWnd"), None) }?;
+    let mut data = APPBARDATA {
+        // `size_of::<APPBARDATA>()` will never exceed u32::MAX
+        #[allow(clippy::cast_possible_truncation)]
+        cbSize: std::mem::size_of::<APPBARDATA>() as u32,
+        hWnd: taskbar,
+        ..Default::default()
+    };
+    // Only the low bits are state flags
+    #[allow(clippy::cast_possible_truncation)]
+    let state = unsafe { SHAppBarMessage(ABM_GETSTATE, &raw mut data) } as u32;
+    #[allow(clippy::cast_possible_wrap)]
+    let new_state = (state ^ ABS_AUTOHIDE) as isize;
+    data.lParam = LPARAM(new_state);
+    unsafe { SHAppBarMessage(ABM_SETSTATE, &raw mut data) };
+    debug!(
+        autohide = state & ABS_AUTOHIDE == 0,
+        "Toggled taskbar auto-hide"
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use windows::Win32::UI::WindowsAndMessaging::{
+        CreateWindowExW, DestroyWindow, GetDesktopWindow, HWND_MESSAGE, WINDOW_EX_STYLE,
+        WINDOW_STYLE,
+    };
+
+    use super::*;
+
+    #[test]
+    fn never_closes_own_windows() {
+        assert!(!may_close(1234, 1234));
+    }
+
+    #[test]
+    fn closes_windows_of_other_processes() {
+        assert!(may_close(5678, 1234));
+    }
+
+    #[test]
+    fn own_hidden_window_is_excluded() {
+        // Like the window of hotcorners-app, never shown and only there to receive messages
+        let hwnd = unsafe {
+            CreateWindowExW(
+                WINDOW_EX_STYLE::default(),
+                w!("STATIC"),
+                w!("hotcorners-test"),
+                WINDOW_STYLE::default(),
+                0,
+                0,
+                0,
+                0,
+                HWND_MESSAGE,
+                None,
+                None,
+                None,
+            )
+        }
+        .expect("a message-only window can be created");
+        let owner = window_process(hwnd);
+        unsafe {
+            let _ = DestroyWindow(hwnd);
+        }
+
+        assert!(!may_close(owner, unsafe { GetCurrentProcessId() }));
+    }
+
+    #[test]
+    fn desktop_window_belongs_to_another_process() {
+        let owner = window_process(unsafe { GetDesktopWindow() });
+        assert!(may_close(owner, unsafe { GetCurrentProcessId() }));
+    }
+}
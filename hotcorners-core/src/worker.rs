@@ -0,0 +1,527 @@
+//! Worker threads that perform the corners' actions
+
+use std::{
+    path::Path,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, OnceLock,
+    },
+    thread,
+    time::Duration,
+};
+
+use tracing::{debug, error, info, trace};
+use windows::Win32::{
+    Foundation::POINT,
+    UI::{
+        Input::KeyboardAndMouse::{SendInput, INPUT},
+        WindowsAndMessaging::GetCursorPos,
+    },
+};
+
+#[cfg(feature = "audio")]
+use crate::audio;
+#[cfg(feature = "wmi")]
+use crate::brightness;
+#[cfg(feature = "focus-assist")]
+use crate::focus_assist;
+#[cfg(feature = "plugins")]
+use crate::plugins;
+#[cfg(feature = "scripting")]
+use crate::scripting;
+use crate::{
+    actions::{self, ActionError},
+    activation_log, com,
+    config::{ActionType, RetryPolicy},
+    corners::{Corner, CornerState, HotZone},
+    display, etw, input, night_light, screenshot,
+    window::{self, FindWindowSpec},
+};
+
+/// How long the cursor has to stay in a corner before it activates, unless configured per corner
+pub const HOT_DELAY: Duration = Duration::from_millis(100);
+
+/// Set by `--simulate`, corners print what they would do instead of doing it
+pub static SIMULATE_MODE: AtomicBool = AtomicBool::new(false);
+
+/// Shows a message about what an action did to the user, the flag is set if the action failed
+type NotifyHandler = Box<dyn Fn(&str, bool) + Send + Sync>;
+
+/// See `set_notify_handler`
+static NOTIFY_HANDLER: OnceLock<NotifyHandler> = OnceLock::new();
+
+/// Sets how messages about what an action did are shown, e.g. as a tray notification. Until it
+/// is set they are only logged.
+pub fn set_notify_handler(handler: impl Fn(&str, bool) + Send + Sync + 'static) {
+    if NOTIFY_HANDLER.set(Box::new(handler)).is_err() {
+        error!("The notification handler is already set");
+    }
+}
+
+/// Shows `message` through the handler set with `set_notify_handler`
+// Only the audio actions report anything yet
+#[cfg_attr(not(feature = "audio"), allow(dead_code))]
+fn notify(message: &str) {
+    if let Some(handler) = NOTIFY_HANDLER.get() {
+        handler(message, false);
+    } else {
+        info!("{message}");
+    }
+}
+
+/// Shows `message` about a failed action through the handler set with `set_notify_handler`, it
+/// was already logged
+fn notify_failure(message: &str) {
+    if let Some(handler) = NOTIFY_HANDLER.get() {
+        handler(message, true);
+    }
+}
+
+/// Creates the state for a corner zone and starts its worker thread
+pub fn spawn_corner(zone: &HotZone<'_>) -> Arc<CornerState> {
+    let corner_config = zone.config;
+    let state = Arc::new(CornerState::new(zone));
+    let action = corner_config.action.clone();
+    let delay = corner_config
+        .delay_ms
+        .map_or(HOT_DELAY, Duration::from_millis);
+
+    let worker_state = Arc::clone(&state);
+    let worker = thread::spawn(move || {
+        let state = worker_state;
+        // Some actions use COM, uninitialized again when the corner is replaced. Simulated
+        // actions don't need it.
+        let _com = if SIMULATE_MODE.load(Ordering::Relaxed) {
+            None
+        } else {
+            com::ComGuard::new()
+                .inspect_err(|e| error!(corner = %state.corner, "Failed to initialize COM: {e}"))
+                .ok()
+        };
+        let input = input::build_action_inputs(&action);
+        loop {
+            while !state.worker.arm_flag.load(Ordering::Acquire) {
+                if state.worker.stop_flag.load(Ordering::Acquire) {
+                    debug!(corner = %state.corner, "Worker exiting");
+                    return;
+                }
+                trace!(corner = %state.corner, "Worker parking");
+                thread::park();
+                debug!(corner = %state.corner, "Worker woke up");
+            }
+            if hot_corner_fn(&state, &action, &input, delay) {
+                state.mark_activated();
+                if !SIMULATE_MODE.load(Ordering::Relaxed) {
+                    activation_log::record(state.corner, &action, delay);
+                }
+            }
+            state.worker.arm_flag.store(false, Ordering::Release);
+        }
+    });
+    state.worker.start(worker);
+
+    state
+}
+
+/// Runs in a corner's worker thread when the cursor enters that corner, waits `delay` and then
+/// performs the corner's configured `action`. `input` is the action's input sequence, built once
+/// when the worker starts.
+///
+/// Returns `false` without doing anything if the cursor left the corner during the delay.
+///
+/// Note: the mouse hook already checked that no modifier keys or mouse buttons are pressed before
+/// waking the worker.
+pub fn hot_corner_fn(
+    state: &CornerState,
+    action: &ActionType,
+    input: &[INPUT],
+    delay: Duration,
+) -> bool {
+    etw::dwell_started(state.corner);
+    thread::sleep(delay);
+    if state.worker.cancel_flag.load(Ordering::Acquire) {
+        debug!(corner = %state.corner, "Cursor left during the delay, cancelled");
+        etw::dwell_cancelled(state.corner);
+        return false;
+    }
+    // The worker may wake up late, after the hook already stopped seeing the cursor in the corner
+    if !cursor_in(state) {
+        debug!(corner = %state.corner, "Cursor no longer in the corner after the delay, cancelled");
+        etw::dwell_cancelled(state.corner);
+        return false;
+    }
+    info!(corner = %state.corner, action = action.name(), "Activating");
+    if SIMULATE_MODE.load(Ordering::Relaxed) {
+        println!("Would fire: {action:?} for corner {}", state.corner);
+        return true;
+    }
+
+    etw::action_started(state.corner, action.name());
+    let mut result = perform_action(state.corner, action, input);
+    if let (Err(ActionError::SendInputFailed { sent, .. }), Some(retry)) =
+        (&result, state.send_input_retry)
+    {
+        result = retry_send_input(state.corner, input, *sent, retry, send_input);
+    }
+    etw::action_finished(state.corner, action.name());
+    state.action_results.record(result.is_ok());
+    if let Err(e) = result {
+        report_failure(state.corner, action, &e);
+    }
+    true
+}
+
+/// Performs `action` for `corner` right away, `input` is its input sequence
+// One arm per action, splitting it up wouldn't make it any easier to follow
+#[allow(clippy::too_many_lines)]
+fn perform_action(corner: Corner, action: &ActionType, input: &[INPUT]) -> Result<(), ActionError> {
+    match action {
+        ActionType::Keystroke(_)
+        | ActionType::ShowDesktop
+        | ActionType::SwitchDesktopLeft
+        | ActionType::SwitchDesktopRight
+        | ActionType::NewDesktop
+        | ActionType::CloseDesktop
+        | ActionType::Copilot
+        | ActionType::RunDialog
+        | ActionType::Search
+        | ActionType::GameBar
+        | ActionType::Keys { .. }
+        | ActionType::TypeText { .. }
+        | ActionType::ScrollWheel { .. } => {
+            let sent = send_input(input);
+            debug!(%corner, events = input.len(), sent, "SendInput");
+            let expected = event_count(input);
+            if sent != expected {
+                return Err(ActionError::SendInputFailed { sent, expected });
+            }
+        }
+        ActionType::Exec { path, args } => launch(path, args)?,
+        #[cfg(feature = "audio")]
+        ActionType::MuteToggle => {
+            audio::toggle_mute().map_err(|e| ActionError::com(&e, "toggle mute"))?;
+        }
+        #[cfg(feature = "audio")]
+        ActionType::VolumeUp { amount } => {
+            audio::change_volume(*amount).map_err(|e| ActionError::com(&e, "raise the volume"))?;
+        }
+        #[cfg(feature = "audio")]
+        ActionType::VolumeDown { amount } => {
+            audio::change_volume(-amount).map_err(|e| ActionError::com(&e, "lower the volume"))?;
+        }
+        #[cfg(feature = "wmi")]
+        ActionType::Brightness { delta } => {
+            brightness::change_brightness(*delta)
+                .map_err(|e| ActionError::com(&e, "change the brightness"))?;
+        }
+        ActionType::ToggleDesktopIcons => {
+            window::toggle_desktop_icons()
+                .map_err(|e| ActionError::win32(&e, "toggle the desktop icons"))?;
+        }
+        ActionType::NightLight { use_registry } => night_light::toggle(*use_registry)?,
+        #[cfg(feature = "focus-assist")]
+        ActionType::FocusAssist => focus_assist::toggle()?,
+        #[cfg(not(feature = "focus-assist"))]
+        ActionType::FocusAssist => {
+            debug!(
+                action = action.name(),
+                "Built without the focus-assist feature, ignored"
+            );
+        }
+        ActionType::OnScreenKeyboard => window::toggle_on_screen_keyboard()?,
+        ActionType::MobilityCenter => window::open_mobility_center()?,
+        ActionType::CloseWindow { confirm } => window::close_foreground_window(*confirm)?,
+        ActionType::WindowState { state } => {
+            window::set_foreground_window_state(*state)
+                .map_err(|e| ActionError::win32(&e, "change the state of the foreground window"))?;
+        }
+        #[cfg(feature = "plugins")]
+        ActionType::Plugin { name } => plugins::execute(name, corner)?,
+        #[cfg(not(feature = "plugins"))]
+        ActionType::Plugin { .. } => {
+            debug!(
+                action = action.name(),
+                "Built without the plugins feature, ignored"
+            );
+        }
+        #[cfg(feature = "scripting")]
+        ActionType::Script { script, path } => {
+            let (result, path) = match (script, path) {
+                (Some(script), _) => (scripting::run(script), None),
+                (None, Some(path)) => (scripting::run_file(path), Some(path)),
+                // Rejected when the configuration was loaded
+                (None, None) => (Ok(()), None),
+            };
+            result.map_err(|message| ActionError::ScriptFailed {
+                path: path.cloned(),
+                message,
+            })?;
+        }
+        #[cfg(not(feature = "scripting"))]
+        ActionType::Script { .. } => {
+            debug!(
+                action = action.name(),
+                "Built without the scripting feature, ignored"
+            );
+        }
+        #[cfg(not(feature = "wmi"))]
+        ActionType::Brightness { .. } => {
+            debug!(
+                action = action.name(),
+                "Built without the wmi feature, ignored"
+            );
+        }
+        #[cfg(feature = "audio")]
+        ActionType::CycleAudioDevice { devices } => {
+            let name = audio::cycle_output_device(devices.as_deref())
+                .map_err(|e| ActionError::com(&e, "change the audio output device"))?;
+            notify(&format!("Audio output: {name}"));
+        }
+        // Already warned about when the configuration was loaded
+        #[cfg(not(feature = "audio"))]
+        ActionType::MuteToggle
+        | ActionType::VolumeUp { .. }
+        | ActionType::VolumeDown { .. }
+        | ActionType::CycleAudioDevice { .. } => {
+            debug!(
+                action = action.name(),
+                "Built without the audio feature, ignored"
+            );
+        }
+        ActionType::Screenshot {
+            save_path,
+            clipboard,
+        } => {
+            screenshot::take_screenshot(save_path.as_deref(), *clipboard)?;
+        }
+        ActionType::LockScreen => {
+            actions::lock_screen().map_err(|e| ActionError::win32(&e, "lock the screen"))?;
+        }
+        ActionType::ToggleWindow {
+            class,
+            title_contains,
+            exec,
+            args,
+        } => {
+            let spec = FindWindowSpec {
+                class: class.as_deref(),
+                title_contains: title_contains.as_deref(),
+            };
+            match (spec.find(), exec) {
+                (Some(hwnd), _) => window::toggle_window(hwnd),
+                (None, Some(path)) => launch(path, args)?,
+                (None, None) => debug!(?spec, "No matching window to toggle"),
+            }
+        }
+        ActionType::OpenUrl { url } => {
+            actions::shell_execute_uri(&actions::percent_encode_url(url))?;
+        }
+        ActionType::Settings { page } => {
+            actions::shell_execute_uri(&format!("ms-settings:{page}"))?;
+        }
+        ActionType::ToggleTaskbarAutohide => {
+            window::toggle_taskbar_autohide()
+                .map_err(|e| ActionError::win32(&e, "toggle taskbar auto-hide"))?;
+        }
+        ActionType::MoveWindowToMonitor { direction, wrap } => {
+            window::move_to_adjacent_monitor(*direction, *wrap)
+                .map_err(|e| ActionError::win32(&e, "move the window to another monitor"))?;
+        }
+        ActionType::DisplayMode { mode } => {
+            display::set_projection_mode(*mode)
+                .map_err(|e| ActionError::win32(&e, "change the display mode"))?;
+        }
+        ActionType::ToggleHdr => {
+            display::toggle_hdr().map_err(|e| ActionError::win32(&e, "toggle HDR"))?;
+        }
+        ActionType::RotateDisplay {
+            degrees,
+            monitor_index,
+        } => display::rotate(*degrees, *monitor_index)?,
+        ActionType::SetResolution {
+            width,
+            height,
+            monitor_name,
+            revert_after_seconds,
+        } => {
+            let revert_after = revert_after_seconds.map(Duration::from_secs);
+            display::set_resolution(*width, *height, monitor_name.as_deref(), revert_after)?;
+        }
+    }
+    Ok(())
+}
+
+/// Sends the events of `input` from index `sent` on again with `send_events` after `SendInput`
+/// left them out, up to `retry.max_retries` times. `send_events` returns how many events it sent,
+/// like `send_input`.
+fn retry_send_input(
+    corner: Corner,
+    input: &[INPUT],
+    mut sent: u32,
+    retry: RetryPolicy,
+    mut send_events: impl FnMut(&[INPUT]) -> u32,
+) -> Result<(), ActionError> {
+    let expected = event_count(input);
+    for attempt in 1..=retry.max_retries {
+        thread::sleep(retry.delay);
+        sent += send_events(&input[sent as usize..]);
+        debug!(%corner, attempt, sent, expected, "Retried SendInput");
+        if sent == expected {
+            return Ok(());
+        }
+    }
+    Err(ActionError::SendInputFailed { sent, expected })
+}
+
+/// Sends `input` with `SendInput`, returning how many events were sent
+fn send_input(input: &[INPUT]) -> u32 {
+    // `size_of::<INPUT>()` will never > i32::MAX
+    #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+    unsafe {
+        SendInput(input, std::mem::size_of::<INPUT>() as i32)
+    }
+}
+
+/// Number of events in `input`
+fn event_count(input: &[INPUT]) -> u32 {
+    // it would be absurd if the size of `input` exceeded `u32::MAX`
+    u32::try_from(input.len()).unwrap_or(u32::MAX)
+}
+
+/// Launches `path` with `args` for an `exec` or `toggle-window` action
+fn launch(path: &Path, args: &[String]) -> Result<(), ActionError> {
+    actions::spawn_detached(path, args).map_err(|error| ActionError::ProcessLaunchFailed {
+        path: path.to_owned(),
+        error,
+    })
+}
+
+/// Logs why the action of `corner` failed, with the details of `error` as fields, and passes it
+/// on to the notification handler
+fn report_failure(corner: Corner, action: &ActionType, error: &ActionError) {
+    let action = action.name();
+    match error {
+        ActionError::SendInputFailed { sent, expected } => {
+            error!(%corner, action, sent, expected, "{error}");
+        }
+        ActionError::ProcessLaunchFailed { path, error: e } => {
+            error!(%corner, action, path = %path.display(), kind = ?e.kind(), "{error}");
+        }
+        ActionError::ComFailed { hresult, context } => {
+            error!(%corner, action, hresult, context, "{error}");
+        }
+        ActionError::Win32Failed { code, context } => {
+            error!(%corner, action, code, context, "{error}");
+        }
+        ActionError::ShellExecuteFailed { uri, error: code } => {
+            error!(%corner, action, uri, code, "{error}");
+        }
+        ActionError::NotFound { what } | ActionError::UnknownFormat { what } => {
+            error!(%corner, action, what, "{error}");
+        }
+        ActionError::ScriptFailed { path, .. } => {
+            error!(%corner, action, path = ?path, "{error}");
+        }
+        ActionError::Io { context, error: e } => {
+            error!(%corner, action, context, kind = ?e.kind(), "{error}");
+        }
+        ActionError::PluginFailed { name, code } => {
+            error!(%corner, action, plugin = name, code, "{error}");
+        }
+        ActionError::TimedOut { what, timeout } => {
+            error!(%corner, action, what, ?timeout, "{error}");
+        }
+    }
+    notify_failure(&format!("The {corner} corner failed: {error}"));
+}
+
+/// Whether the cursor is currently inside the corner of `state`
+fn cursor_in(state: &CornerState) -> bool {
+    let mut pos = POINT::default();
+    unsafe { GetCursorPos(&raw mut pos) }.is_ok() && state.contains(pos)
+}
+
+#[cfg(test)]
+mod tests {
+    use windows::Win32::UI::Input::KeyboardAndMouse::{KEYBD_EVENT_FLAGS, VIRTUAL_KEY};
+
+    use super::*;
+
+    /// Retries right away, up to `max_retries` times
+    fn retry(max_retries: u32) -> RetryPolicy {
+        RetryPolicy {
+            max_retries,
+            delay: Duration::ZERO,
+        }
+    }
+
+    /// Four key presses with the virtual keys 1 to 4, so each event can be told apart
+    fn input() -> Vec<INPUT> {
+        (1..=4)
+            .map(|vk| input::keyboard_input(VIRTUAL_KEY(vk), KEYBD_EVENT_FLAGS(0)))
+            .collect()
+    }
+
+    /// The virtual keys of `input`
+    fn vks(input: &[INPUT]) -> Vec<u16> {
+        input
+            .iter()
+            .map(|input| unsafe { input.Anonymous.ki.wVk.0 })
+            .collect()
+    }
+
+    #[test]
+    fn retries_only_the_events_that_were_left_out() {
+        let mut attempts = Vec::new();
+        let result = retry_send_input(Corner::TopLeft, &input(), 1, retry(3), |rest| {
+            attempts.push(vks(rest));
+            1
+        });
+
+        assert!(result.is_ok());
+        assert_eq!(attempts, [vec![2, 3, 4], vec![3, 4], vec![4]]);
+    }
+
+    #[test]
+    fn stops_retrying_once_everything_was_sent() {
+        let mut attempts = Vec::new();
+        let result = retry_send_input(Corner::TopLeft, &input(), 2, retry(3), |rest| {
+            attempts.push(vks(rest));
+            event_count(rest)
+        });
+
+        assert!(result.is_ok());
+        assert_eq!(attempts, [vec![3, 4]]);
+    }
+
+    #[test]
+    fn gives_up_after_max_retries() {
+        let mut attempts = 0;
+        let result = retry_send_input(Corner::TopLeft, &input(), 1, retry(2), |_| {
+            attempts += 1;
+            0
+        });
+
+        assert_eq!(attempts, 2);
+        assert!(matches!(
+            result,
+            Err(ActionError::SendInputFailed {
+                sent: 1,
+                expected: 4
+            })
+        ));
+    }
+
+    #[test]
+    fn gives_up_reporting_the_events_sent_so_far() {
+        let result = retry_send_input(Corner::TopLeft, &input(), 0, retry(2), |_| 1);
+
+        assert!(matches!(
+            result,
+            Err(ActionError::SendInputFailed {
+                sent: 2,
+                expected: 4
+            })
+        ));
+    }
+}